@@ -17,10 +17,26 @@ pub struct Account {
     pub bio: String,
 
     pub joined_communities: UnorderedSet<CommunityId>,
+    pub followed_topics: UnorderedSet<TopicId>,
+
+    /// Blocked accounts can't follow, message or comment on this account.
+    pub blocked: UnorderedSet<AccountId>,
+    /// Muted accounts are only hidden from this account.
+    pub muted: UnorderedSet<AccountId>,
+
+    pub reputation: i64,
+
+    /// Number of notifications received, the ids below `notifications_read` were read.
+    pub num_notifications: u64,
+    pub notifications_read: u64,
+    pub notification_opt_outs: Vec<NotificationType>,
 }
 
+/// V1 accounts are only read, they are always written back as `Last`.
+#[allow(clippy::large_enum_variant)]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub enum VAccount {
+    V1(AccountV1),
     Last(Account),
 }
 
@@ -30,9 +46,10 @@ impl From<Account> for VAccount {
     }
 }
 
-impl From<VAccount> for Account {
-    fn from(v_account: VAccount) -> Self {
-        match v_account {
+impl VAccount {
+    pub(crate) fn into_account(self, account_id: &AccountId) -> Account {
+        match self {
+            VAccount::V1(account) => account.into_account(account_id),
             VAccount::Last(account) => account,
         }
     }
@@ -45,6 +62,7 @@ pub struct AccountStats {
     pub num_following: u64,
     pub related_conversations: Vec<MessageId>,
     pub message_pub_key: String,
+    pub reputation: i64,
 
     pub avatar: String,
     pub thumbnail: String,
@@ -59,6 +77,7 @@ impl From<Account> for AccountStats {
             num_following: account.following.len(),
             related_conversations: account.related_conversations.to_vec(),
             message_pub_key: account.message_pub_key,
+            reputation: account.reputation,
 
             avatar: account.avatar,
             thumbnail: account.thumbnail,
@@ -86,8 +105,8 @@ impl Contract {
                 let mut community: WrappedCommunity = self.communities.get(&id).unwrap().into();
 
                 community.posts_count =
-                    if let Some(communites_posts) = self.communities_posts.get(&id) {
-                        communites_posts.len() as u16
+                    if let Some(community_posts) = self.community_posts.get(&id) {
+                        community_posts.len() as u16
                     } else {
                         0
                     };
@@ -104,6 +123,7 @@ impl Contract {
             &account_id, &from_account_id,
             "Can't follow your own account"
         );
+        self.assert_not_blocked(&account_id, &from_account_id);
 
         let storage_update = self.new_storage_update(from_account_id.clone());
         let mut from_account = self.internal_get_account(&from_account_id);
@@ -119,7 +139,15 @@ impl Contract {
             "Already followed by your account"
         );
         self.internal_set_account(&account_id, account);
+        self.internal_add_reputation(&account_id, FOLLOWER_REPUTATION);
+        self.internal_notify(&account_id, &from_account_id, NotificationKind::Follow);
         self.finalize_storage_update(storage_update);
+
+        Event::Follow(FollowData {
+            account_id: &account_id,
+            follower_id: &from_account_id,
+        })
+        .emit();
     }
 
     pub fn unfollow(&mut self, account_id: String) {
@@ -144,7 +172,14 @@ impl Contract {
             "Not followed by your account"
         );
         self.internal_set_account(&account_id, account);
+        self.internal_add_reputation(&account_id, -FOLLOWER_REPUTATION);
         self.finalize_storage_update(storage_update);
+
+        Event::Unfollow(FollowData {
+            account_id: &account_id,
+            follower_id: &from_account_id,
+        })
+        .emit();
     }
 
     pub fn set_avatar(&mut self, avatar: String) {
@@ -247,6 +282,7 @@ impl Contract {
         self.finalize_storage_update(storage_update);
     }
 
+    /// Deleted posts and posts of private communities the account left are skipped.
     pub fn get_bookmarks(
         &self,
         account_id: ValidAccountId,
@@ -255,25 +291,27 @@ impl Contract {
     ) -> Vec<Post> {
         let bookmarks = self.internal_get_account(account_id.as_ref()).bookmarks;
         calculate_rev_limit(bookmarks.len() as u64, from_index, limit)
-            .map(|index| {
+            .filter_map(|index| {
                 let post_id = bookmarks.get(index as usize).unwrap();
-                self.posts.get(&post_id).expect("Post not found").into()
+                self.internal_get_post(post_id)
             })
+            .filter(|post| self.internal_can_access_post(post, Some(account_id.as_ref())))
             .rev()
             .collect()
     }
 
-    pub fn top_users(&self) -> Vec<AccountStats> {
-        let mut result: Vec<AccountStats> = self
-            .accounts
-            .iter()
-            .map(|(_id, item)| {
-                let account: Account = item.into();
-                account.into()
+    /// Accounts with the highest reputation, best first.
+    pub fn top_users(&self, limit: u64) -> Vec<(AccountId, AccountStats)> {
+        self.user_leaderboard
+            .get()
+            .unwrap_or_default()
+            .into_iter()
+            .take(limit as usize)
+            .map(|entry| {
+                let account = self.internal_get_account(&entry.account_id);
+                (entry.account_id, account.into())
             })
-            .collect();
-
-        result.drain(..std::cmp::min(8, result.len())).collect()
+            .collect()
     }
 
     pub fn get_followers(
@@ -319,7 +357,7 @@ impl Contract {
         (from_index..std::cmp::min(from_index + limit, account_ids.len()))
             .map(|index| {
                 let account_id = account_ids.get(index).unwrap();
-                let account: Account = accounts.get(index).unwrap().into();
+                let account = accounts.get(index).unwrap().into_account(&account_id);
                 (account_id, account.into())
             })
             .collect()
@@ -333,8 +371,7 @@ impl Contract {
         account_ids
             .iter()
             .map(|id| {
-                let account: Account = self.accounts.get(id).unwrap().into();
-                account.into()
+                self.internal_get_account(id).into()
             })
             .collect()
     }
@@ -0,0 +1,273 @@
+use super::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Also removes the follow relationship in both directions.
+    pub fn block_account(&mut self, account_id: ValidAccountId) {
+        let account_id: AccountId = account_id.into();
+        let from_account_id = env::predecessor_account_id();
+        assert_ne!(
+            &account_id, &from_account_id,
+            "Can't block your own account"
+        );
+
+        let storage_update = self.new_storage_update(from_account_id.clone());
+        let mut from_account = self.internal_get_account(&from_account_id);
+        assert!(
+            from_account.blocked.insert(&account_id),
+            "Already blocked this account"
+        );
+        let unfollowed = from_account.following.remove(&account_id);
+        let removed_follower = from_account.followers.remove(&account_id);
+        self.internal_set_account(&from_account_id, from_account);
+
+        if let Some(mut account) = self.internal_get_account_optional(&account_id) {
+            account.followers.remove(&from_account_id);
+            account.following.remove(&from_account_id);
+            self.internal_set_account(&account_id, account);
+        }
+        if unfollowed {
+            self.internal_add_reputation(&account_id, -FOLLOWER_REPUTATION);
+        }
+        if removed_follower {
+            self.internal_add_reputation(&from_account_id, -FOLLOWER_REPUTATION);
+        }
+        self.finalize_storage_update(storage_update);
+
+        if unfollowed {
+            Event::Unfollow(FollowData {
+                account_id: &account_id,
+                follower_id: &from_account_id,
+            })
+            .emit();
+        }
+        if removed_follower {
+            Event::Unfollow(FollowData {
+                account_id: &from_account_id,
+                follower_id: &account_id,
+            })
+            .emit();
+        }
+    }
+
+    pub fn unblock_account(&mut self, account_id: ValidAccountId) {
+        let from_account_id = env::predecessor_account_id();
+
+        let storage_update = self.new_storage_update(from_account_id.clone());
+        let mut from_account = self.internal_get_account(&from_account_id);
+        assert!(
+            from_account.blocked.remove(account_id.as_ref()),
+            "Not blocking this account"
+        );
+        self.internal_set_account(&from_account_id, from_account);
+        self.finalize_storage_update(storage_update);
+    }
+
+    /// Unlike a block, a mute doesn't reject anything: the muted account's posts are left out
+    /// of the home feed and its actions don't notify.
+    pub fn mute_account(&mut self, account_id: ValidAccountId) {
+        let account_id: AccountId = account_id.into();
+        let from_account_id = env::predecessor_account_id();
+        assert_ne!(&account_id, &from_account_id, "Can't mute your own account");
+
+        let storage_update = self.new_storage_update(from_account_id.clone());
+        let mut from_account = self.internal_get_account(&from_account_id);
+        assert!(
+            from_account.muted.insert(&account_id),
+            "Already muted this account"
+        );
+        self.internal_set_account(&from_account_id, from_account);
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn unmute_account(&mut self, account_id: ValidAccountId) {
+        let from_account_id = env::predecessor_account_id();
+
+        let storage_update = self.new_storage_update(from_account_id.clone());
+        let mut from_account = self.internal_get_account(&from_account_id);
+        assert!(
+            from_account.muted.remove(account_id.as_ref()),
+            "Not muting this account"
+        );
+        self.internal_set_account(&from_account_id, from_account);
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn is_blocked(&self, account_id: ValidAccountId, by: ValidAccountId) -> bool {
+        self.internal_is_blocked(by.as_ref(), account_id.as_ref())
+    }
+
+    pub fn get_blocked(
+        &self,
+        account_id: ValidAccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        let account = self.internal_get_account(account_id.as_ref());
+        let blocked = account.blocked.as_vector();
+        (from_index..std::cmp::min(from_index + limit, blocked.len()))
+            .map(|index| blocked.get(index).unwrap())
+            .collect()
+    }
+
+    pub fn get_muted(
+        &self,
+        account_id: ValidAccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        let account = self.internal_get_account(account_id.as_ref());
+        let muted = account.muted.as_vector();
+        (from_index..std::cmp::min(from_index + limit, muted.len()))
+            .map(|index| muted.get(index).unwrap())
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Whether `account_id` has blocked `other_account_id`.
+    pub(crate) fn internal_is_blocked(
+        &self,
+        account_id: &AccountId,
+        other_account_id: &AccountId,
+    ) -> bool {
+        self.internal_get_account_optional(account_id)
+            .map(|account| account.blocked.contains(other_account_id))
+            .unwrap_or(false)
+    }
+
+    /// Panics if `account_id` has blocked `caller_id`.
+    pub(crate) fn assert_not_blocked(&self, account_id: &AccountId, caller_id: &AccountId) {
+        assert!(
+            !self.internal_is_blocked(account_id, caller_id),
+            "You're blocked by this account"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Bob blocks charlie.
+    fn setup_block() -> Contract {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.block_account(accounts(2));
+        contract
+    }
+
+    #[test]
+    fn test_block_removes_follows_and_unblock() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.follow(accounts(2));
+        testing_env!(context(2, NOW).build());
+        contract.follow(accounts(1));
+
+        testing_env!(context(1, NOW).build());
+        contract.block_account(accounts(2));
+        assert!(contract.is_blocked(accounts(2), accounts(1)));
+        assert!(!contract.is_blocked(accounts(1), accounts(2)));
+        assert_eq!(
+            contract.get_blocked(accounts(1), 0, 10),
+            vec![AccountId::from(accounts(2))]
+        );
+        let bob = contract.internal_get_account(&accounts(1).into());
+        assert_eq!(bob.following.len() + bob.followers.len(), 0);
+        let charlie = contract.internal_get_account(&accounts(2).into());
+        assert_eq!(charlie.following.len() + charlie.followers.len(), 0);
+
+        contract.unblock_account(accounts(2));
+        assert!(contract.get_blocked(accounts(1), 0, 10).is_empty());
+        testing_env!(context(2, NOW).build());
+        contract.follow(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_follow() {
+        let mut contract = setup_block();
+        testing_env!(context(2, NOW).build());
+        contract.follow(accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_message() {
+        let mut contract = setup_block();
+        testing_env!(context(2, NOW).build());
+        contract.new_message(
+            accounts(1).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_comment() {
+        let mut contract = setup_block();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.comment(post.id, IPFS_BODY.to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_join_community() {
+        let mut contract = setup_block();
+        let community_id = new_community(&mut contract, 1, "Rust", CommunityVisibility::Public);
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_invite() {
+        let mut contract = setup_block();
+        let community_id = new_community(&mut contract, 2, "Rust", CommunityVisibility::InviteOnly);
+        contract.invite_member(community_id, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're blocked by this account")]
+    fn test_blocked_account_can_not_add_to_group() {
+        let mut contract = setup_block();
+        testing_env!(context(2, NOW).build());
+        contract.new_group_conversation("friends".to_string(), vec![accounts(1)]);
+    }
+
+    #[test]
+    fn test_mute_only_silences() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.mute_account(accounts(2));
+        assert_eq!(
+            contract.get_muted(accounts(1), 0, 10),
+            vec![AccountId::from(accounts(2))]
+        );
+
+        // The muted account can still interact, without notifying.
+        testing_env!(context(2, NOW).build());
+        contract.follow(accounts(1));
+        contract.new_message(
+            accounts(1).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+        assert_eq!(contract.get_unread_notification_count(accounts(1)), 0);
+
+        testing_env!(context(1, NOW).build());
+        contract.unmute_account(accounts(2));
+        assert!(contract.get_muted(accounts(1), 0, 10).is_empty());
+        testing_env!(context(2, NOW).block_index(1).build());
+        contract.new_message(
+            accounts(1).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+        assert_eq!(contract.get_unread_notification_count(accounts(1)), 1);
+    }
+}
@@ -1,26 +1,97 @@
 use crate::*;
 
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Can delete any post, community post or comment.
+    Moderator,
+    /// Can manage topics created by other accounts.
+    TopicCurator,
+    /// Can edit and delete any chest.
+    ChestOperator,
+}
+
+pub const ROLES: [Role; 3] = [Role::Moderator, Role::TopicCurator, Role::ChestOperator];
+
 #[near_bindgen]
 impl Contract {
+    pub fn get_owner(&self) -> AccountId {
+        self.owner_id.clone()
+    }
+
+    pub fn get_pending_owner(&self) -> Option<AccountId> {
+        self.pending_owner_id.clone()
+    }
+
+    /// First step of the ownership transfer, the new owner has to call `accept_ownership`.
+    pub fn propose_owner(&mut self, new_owner_id: ValidAccountId) {
+        self.assert_owner();
+        let new_owner_id: AccountId = new_owner_id.into();
+        assert_ne!(
+            new_owner_id, self.owner_id,
+            "This account is already the owner"
+        );
+
+        Event::OwnerPropose(OwnerData {
+            owner_id: &self.owner_id,
+            new_owner_id: &new_owner_id,
+        })
+        .emit();
+        self.pending_owner_id = Some(new_owner_id);
+    }
+
+    pub fn cancel_owner_proposal(&mut self) {
+        self.assert_owner();
+        assert!(self.pending_owner_id.is_some(), "No pending owner");
+        self.pending_owner_id = None;
+    }
+
+    pub fn accept_ownership(&mut self) {
+        let caller_id = env::predecessor_account_id();
+        assert_eq!(
+            Some(caller_id.clone()),
+            self.pending_owner_id,
+            "You are not the pending owner"
+        );
+
+        Event::OwnerTransfer(OwnerData {
+            owner_id: &self.owner_id,
+            new_owner_id: &caller_id,
+        })
+        .emit();
+        self.owner_id = caller_id;
+        self.pending_owner_id = None;
+    }
+
     pub fn add_admin(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
         let caller_id = env::predecessor_account_id();
-        let contract_id = env::current_account_id();
         assert!(
-            caller_id == "neilharan.testnet" 
-            || caller_id == contract_id,
-            "You not have permission to give admin rights"); 
-        assert!(!self.is_admin(account_id.clone()), "This account already have admin rights");
+            !self.is_admin(account_id.clone()),
+            "This account already have admin rights"
+        );
+
+        Event::AdminAdd(AdminData {
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
         self.admins.insert(&account_id)
     }
 
     pub fn remove_admin(&mut self, account_id: AccountId) -> bool {
+        self.assert_owner();
         let caller_id = env::predecessor_account_id();
-        let contract_id = env::current_account_id();
         assert!(
-            caller_id == "neilharan.testnet" 
-            || caller_id == contract_id,
-            "You not have permission to remove admin rights"); 
-        assert!(self.is_admin(account_id.clone()), "This account not have admin rights");
+            self.is_admin(account_id.clone()),
+            "This account not have admin rights"
+        );
+
+        Event::AdminRemove(AdminData {
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
         self.admins.remove(&account_id)
     }
 
@@ -28,28 +99,157 @@ impl Contract {
         self.admins.contains(&account_id)
     }
 
-    // //NOTE: Migrate function
-    // #[private]
-    // #[init(ignore_state)]
-    // pub fn migrate(&self) -> Self {
-    //     assert!(self.is_admin(env::predecessor_account_id()), "You don't have permission to migrate this contract!");
-    //     let old_state: OldContract = env::state_read().expect("failed");
-    //     Self {
-    //         storage_accounts: old_state.storage_accounts,
-    //         accounts: old_state.accounts,
-    //         posts: old_state.posts,
-    //         user_posts: old_state.user_posts,
-    //         deleted_posts: old_state.deleted_posts,
-    //         messages: old_state.messages,
-    //         likes: old_state.likes,
-    //         comments: old_state.comments,
-    //         topics: old_state.topics,
-    //         topics_posts: old_state.topics_posts,
-    //         communities: old_state.communities,
-    //         communities_posts: old_state.communities_posts,
-    //         members_in_communites: old_state.members_in_communites,
-    //         storage_account_in_bytes: old_state.storage_account_in_bytes,
-    //         admins: LookupSet::new(StorageKey::Admins),
-    //     }
-    // }
+    /// Only the owner can grant roles.
+    pub fn grant_role(&mut self, account_id: ValidAccountId, role: Role) -> bool {
+        self.assert_owner();
+        let caller_id = env::predecessor_account_id();
+
+        let account_id: AccountId = account_id.into();
+        let mut members = self
+            .roles
+            .get(&role)
+            .unwrap_or_else(|| UnorderedSet::new(StorageKey::RolesInner { role }));
+        assert!(
+            members.insert(&account_id),
+            "This account already have this role"
+        );
+        self.roles.insert(&role, &members);
+
+        Event::RoleGrant(RoleData {
+            account_id: &account_id,
+            role,
+            by: &caller_id,
+        })
+        .emit();
+        true
+    }
+
+    pub fn revoke_role(&mut self, account_id: ValidAccountId, role: Role) -> bool {
+        self.assert_owner();
+        let caller_id = env::predecessor_account_id();
+
+        let account_id: AccountId = account_id.into();
+        let mut members = self
+            .roles
+            .get(&role)
+            .expect("This account not have this role");
+        assert!(
+            members.remove(&account_id),
+            "This account not have this role"
+        );
+        self.roles.insert(&role, &members);
+
+        Event::RoleRevoke(RoleData {
+            account_id: &account_id,
+            role,
+            by: &caller_id,
+        })
+        .emit();
+        true
+    }
+
+    pub fn has_role(&self, account_id: AccountId, role: Role) -> bool {
+        self.roles
+            .get(&role)
+            .map(|members| members.contains(&account_id))
+            .unwrap_or(false)
+    }
+
+    pub fn get_roles(&self, account_id: AccountId) -> Vec<Role> {
+        ROLES
+            .iter()
+            .filter(|role| self.has_role(account_id.clone(), **role))
+            .cloned()
+            .collect()
+    }
+
+    pub fn get_role_members(&self, role: Role, from_index: u64, limit: u64) -> Vec<AccountId> {
+        match self.roles.get(&role) {
+            Some(members) => {
+                let members = members.as_vector();
+                (from_index..std::cmp::min(from_index + limit, members.len()))
+                    .map(|index| members.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn assert_owner(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.owner_id,
+            "Only the owner can call this method"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_two_step_ownership_transfer() {
+        let mut contract = setup();
+        contract.propose_owner(accounts(1));
+        assert_eq!(contract.get_pending_owner(), Some("bob".to_string()));
+        contract.cancel_owner_proposal();
+        assert!(contract.get_pending_owner().is_none());
+
+        contract.propose_owner(accounts(2));
+        testing_env!(context(2, NOW).build());
+        contract.accept_ownership();
+        assert_eq!(contract.get_owner(), "charlie".to_string());
+        assert!(contract.get_pending_owner().is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the pending owner")]
+    fn test_only_pending_owner_can_accept() {
+        let mut contract = setup();
+        contract.propose_owner(accounts(1));
+        testing_env!(context(2, NOW).build());
+        contract.accept_ownership();
+    }
+
+    #[test]
+    fn test_grant_and_revoke_roles() {
+        let mut contract = setup();
+        contract.grant_role(accounts(2), Role::Moderator);
+        assert!(contract.has_role(accounts(2).into(), Role::Moderator));
+        assert_eq!(
+            contract.get_role_members(Role::Moderator, 0, 10),
+            vec!["charlie".to_string()]
+        );
+
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.delete_post(post.id);
+
+        testing_env!(context(0, NOW).build());
+        contract.revoke_role(accounts(2), Role::Moderator);
+        assert!(contract.get_roles(accounts(2).into()).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_admins_can_not_grant_roles() {
+        let mut contract = setup();
+        contract.add_admin(accounts(1).into());
+        testing_env!(context(1, NOW).build());
+        contract.grant_role(accounts(2), Role::Moderator);
+    }
+
+    #[test]
+    #[should_panic(expected = "You don't have permission to delete")]
+    fn test_admins_need_the_moderator_role_to_delete_posts() {
+        let mut contract = setup();
+        contract.add_admin(accounts(2).into());
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.delete_post(post.id);
+    }
 }
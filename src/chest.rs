@@ -153,6 +153,12 @@ impl Contract {
         chests_at_place.insert(&chest_id);
         self.chests_per_place.insert(&place_id, &chests_at_place);
 
+        Event::ChestPlace(ChestData {
+            chest_id: &chest_id,
+            account_id: &account_id,
+            place_id: &place_id,
+        })
+        .emit();
         chest
     }
 
@@ -222,6 +228,12 @@ impl Contract {
         chests_at_place.insert(&chest_id);
         self.chests_per_place.insert(&place_id, &chests_at_place);
 
+        Event::ChestPlace(ChestData {
+            chest_id: &chest_id,
+            account_id: &account_id,
+            place_id: &place_id,
+        })
+        .emit();
         chest
     }
 
@@ -300,6 +312,18 @@ impl Contract {
         chests_at_place.insert(&new_chest_id);
         self.chests_per_place.insert(&place_id, &chests_at_place);
 
+        Event::ChestDelete(ChestData {
+            chest_id: &chest_id,
+            account_id: &account_id,
+            place_id: &old_chest.location.label,
+        })
+        .emit();
+        Event::ChestPlace(ChestData {
+            chest_id: &new_chest_id,
+            account_id: &account_id,
+            place_id: &place_id,
+        })
+        .emit();
         chest
     }
 
@@ -310,8 +334,9 @@ impl Contract {
         let mut chest = self.chests.get(&chest_id).expect("Chest not found");
 
         assert!(
-            account_id == chest.sender_account_id || self.is_admin(account_id),
-            "Just owner or admin can edit chest information"
+            account_id == chest.sender_account_id
+                || self.has_role(account_id.clone(), Role::ChestOperator),
+            "Just owner or chest operator can edit chest information"
         );
 
         // Change place_id
@@ -352,6 +377,12 @@ impl Contract {
         chest.location = new_location;
         self.chests.insert(&chest_id, &chest);
 
+        Event::ChestEdit(ChestData {
+            chest_id: &chest_id,
+            account_id: &account_id,
+            place_id: &chest.location.label,
+        })
+        .emit();
         chest
     }
 
@@ -361,18 +392,20 @@ impl Contract {
         let account_id = env::predecessor_account_id();
 
         assert!(
-            account_id == chest.sender_account_id || self.is_admin(account_id.clone()),
-            "Just owner or admin can edit chest information"
+            account_id == chest.sender_account_id
+                || self.has_role(account_id.clone(), Role::ChestOperator),
+            "Just owner or chest operator can edit chest information"
         );
 
         // Remove in chests
         self.chests.remove(&chest_id);
 
         // Remove chest in account info
-        let mut account = self.internal_get_account(&account_id.clone());
-        let index = account.chests.iter().position(|x| *x == chest_id).unwrap();
-        account.chests.remove(index);
-        self.internal_set_account(&account_id, account);
+        let mut account = self.internal_get_account(&chest.sender_account_id);
+        if let Some(index) = account.chests.iter().position(|x| *x == chest_id) {
+            account.chests.remove(index);
+        }
+        self.internal_set_account(&chest.sender_account_id, account);
 
         // Remove chest in chest per place
         let mut list_chests_at_place = self
@@ -390,6 +423,12 @@ impl Contract {
                 .insert(&chest.location.label, &list_chests_at_place);
         }
 
+        Event::ChestDelete(ChestData {
+            chest_id: &chest_id,
+            account_id: &account_id,
+            place_id: &chest.location.label,
+        })
+        .emit();
         chest
     }
 }
@@ -1,50 +1,139 @@
 use super::*;
 
+pub type CommentId = u64;
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Comment {
-    owner: AccountId,
-    body: String,
-    time: U64,
+    pub id: CommentId,
+    pub parent_id: Option<CommentId>,
+    pub owner: AccountId,
+    pub body: String,
+    pub time: U64,
+    pub deleted: bool,
+    pub score: i64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "type")]
+pub enum CommentSort {
+    Newest,
+    Oldest,
+    /// Highest score first, newest first among equal scores.
+    Top,
+}
+
+/// One page of live comments, deleted ones are skipped.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommentPage {
+    pub comments: Vec<Comment>,
+    /// Pass as `cursor` to load the next page, `None` once the last comment was reached.
+    pub cursor: Option<u64>,
 }
 
 #[near_bindgen]
 impl Contract {
-    pub fn comment(&mut self, post_id: PostId, body: String) -> Comment {
+    pub fn comment(
+        &mut self,
+        post_id: PostId,
+        body: String,
+        parent_id: Option<CommentId>,
+    ) -> Comment {
         assert!(
             body.len() <= IPFS_HASH_LENGTH,
             "Body should be an ipfs hash!!",
         );
 
         let account_id = env::predecessor_account_id();
+        let post = self.internal_get_post(&post_id).expect("Post not found");
+        self.assert_not_blocked(&post.account_id, &account_id);
+        self.assert_can_interact_with_post(&post, &account_id);
+        let post_owner = post.account_id;
         let storage_update = self.new_storage_update(account_id.clone());
 
+        let mut values = self.comments.get(&post_id).unwrap_or_else(|| {
+            let mut comment_key = vec![b'c'];
+
+            let hash = env::sha256(post_id.as_bytes());
+            comment_key.extend_from_slice(&hash);
+
+            Vector::new(comment_key)
+        });
+
+        let parent_owner = parent_id.map(|parent_id| {
+            let parent = values.get(parent_id).expect("Parent comment not found");
+            assert!(!parent.deleted, "Can't reply to a deleted comment");
+            self.assert_not_blocked(&parent.owner, &account_id);
+            parent.owner
+        });
+
+        // Comments are never removed from the vector, so the index is a stable id.
         let comment = Comment {
+            id: values.len(),
+            parent_id,
             owner: account_id,
             body,
             time: env::block_timestamp().into(),
+            deleted: false,
+            score: 0,
         };
 
-        if let Some(mut values) = self.comments.get(&post_id) {
-            values.push(&comment);
-            self.comments.insert(&post_id, &values);
-        } else {
-            let mut comment_key = vec![b'c'];
+        values.push(&comment);
+        self.comments.insert(&post_id, &values);
+        self.internal_rank_comment(&post_id, &comment);
 
-            let hash = env::sha256(post_id.as_bytes());
-            comment_key.extend_from_slice(&hash);
+        if let Some(parent_id) = parent_id {
+            let key = (post_id.clone(), parent_id);
+            let mut replies = self.comment_replies.get(&key).unwrap_or_else(|| {
+                Vector::new(StorageKey::CommentRepliesInner {
+                    post_id: post_id.clone(),
+                    comment_id: parent_id,
+                })
+            });
+            replies.push(&comment.id);
+            self.comment_replies.insert(&key, &replies);
+        }
 
-            let mut values: Vector<Comment> = Vector::new(comment_key);
+        self.internal_add_commenter(&post_id, &post_owner, &comment.owner);
 
-            values.push(&comment);
-            self.comments.insert(&post_id, &values);
+        // The author of the post and of the replied comment are notified once each.
+        let mut recipients = vec![post_owner];
+        if let Some(parent_owner) = parent_owner {
+            if !recipients.contains(&parent_owner) {
+                recipients.push(parent_owner);
+            }
+        }
+        for recipient in recipients {
+            self.internal_notify(
+                &recipient,
+                &comment.owner,
+                NotificationKind::Comment {
+                    post_id: post_id.clone(),
+                    comment_id: comment.id,
+                },
+            );
         }
 
         self.finalize_storage_update(storage_update);
+
+        Event::CommentCreate(CommentData {
+            post_id: &post_id,
+            comment_id: comment.id,
+            parent_id: comment.parent_id,
+            account_id: &comment.owner,
+        })
+        .emit();
         comment
     }
 
-    pub fn edit_comment(&mut self, post_id: PostId, comment_index: u64, body: String) -> Comment {
+    pub fn edit_comment(
+        &mut self,
+        post_id: PostId,
+        comment_id: CommentId,
+        body: String,
+    ) -> Comment {
         let account_id = env::predecessor_account_id();
         assert!(
             body.len() <= IPFS_HASH_LENGTH,
@@ -57,7 +146,8 @@ impl Contract {
             .get(&post_id)
             .expect("Not found comments in post");
 
-        let mut comment = comments.get(comment_index).expect("Out of bound");
+        let mut comment = comments.get(comment_id).expect("Out of bound");
+        assert!(!comment.deleted, "Comment was deleted");
         assert!(
             comment.owner == account_id,
             "You don't have permission to edit this comment"
@@ -65,35 +155,197 @@ impl Contract {
 
         comment.body = body;
         comment.time = env::block_timestamp().into();
-        comments.replace(comment_index, &comment);
+        comments.replace(comment_id, &comment);
         self.comments.insert(&post_id, &comments);
 
         self.finalize_storage_update(storage_update);
+
+        Event::CommentEdit(CommentData {
+            post_id: &post_id,
+            comment_id,
+            parent_id: comment.parent_id,
+            account_id: &account_id,
+        })
+        .emit();
         comment
     }
 
-    pub fn get_comments(&self, post_id: PostId, from_index: u64, limit: u64) -> Vec<Comment> {
+    /// Comment owner, post author or moderator can delete a comment.
+    /// The comment is kept as a tombstone so its id and replies stay valid.
+    pub fn delete_comment(&mut self, post_id: PostId, comment_id: CommentId) {
+        let account_id = env::predecessor_account_id();
+
+        let mut comments = self
+            .comments
+            .get(&post_id)
+            .expect("Not found comments in post");
+
+        let mut comment = comments.get(comment_id).expect("Comment not found");
+        assert!(!comment.deleted, "Comment was already deleted");
+
+        let post_owner = self.internal_get_post(&post_id).map(|post| post.account_id);
+        let is_post_author = post_owner.as_ref() == Some(&account_id);
+
+        assert!(
+            comment.owner == account_id
+                || is_post_author
+                || self.has_role(account_id.clone(), Role::Moderator),
+            "You don't have permission to delete this comment"
+        );
+
+        // The freed bytes go back to the author of the comment.
+        let storage_update = self.new_storage_update(comment.owner.clone());
+        comment.body = "".to_string();
+        comment.deleted = true;
+        comments.replace(comment_id, &comment);
+        self.comments.insert(&post_id, &comments);
+        self.internal_unrank_comment(&post_id, comment_id);
+
+        if let Some(post_owner) = &post_owner {
+            self.internal_remove_commenter(&post_id, post_owner, &comment.owner);
+        }
+        self.finalize_storage_update(storage_update);
+
+        Event::CommentDelete(CommentData {
+            post_id: &post_id,
+            comment_id,
+            parent_id: comment.parent_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    /// Newest first unless another `sort` is given. `cursor` is a comment id for `Newest` and
+    /// `Oldest`, a position for `Top`. A page scans at most `MAX_FEED_SCAN` comments, so it can
+    /// come back short with a cursor. `Top` only lists the `MAX_TOP_COMMENTS` ranked comments.
+    pub fn get_comments(
+        &self,
+        post_id: PostId,
+        cursor: Option<u64>,
+        limit: u64,
+        sort: Option<CommentSort>,
+    ) -> CommentPage {
         let comments = self
             .comments
             .get(&post_id)
             .unwrap_or_else(|| Vector::new(b"v"));
+        let len = comments.len();
+        let mut page = vec![];
+
+        match sort.unwrap_or(CommentSort::Newest) {
+            CommentSort::Newest => {
+                let mut index = cursor.unwrap_or(len).min(len);
+                let stop = index.saturating_sub(MAX_FEED_SCAN);
+                while index > stop && (page.len() as u64) < limit {
+                    index -= 1;
+                    let comment = comments.get(index).unwrap();
+                    if !comment.deleted {
+                        page.push(comment);
+                    }
+                }
+                CommentPage {
+                    comments: page,
+                    cursor: if index > 0 { Some(index) } else { None },
+                }
+            }
+            CommentSort::Oldest => {
+                let mut index = cursor.unwrap_or(0);
+                let stop = std::cmp::min(index + MAX_FEED_SCAN, len);
+                while index < stop && (page.len() as u64) < limit {
+                    let comment = comments.get(index).unwrap();
+                    if !comment.deleted {
+                        page.push(comment);
+                    }
+                    index += 1;
+                }
+                CommentPage {
+                    comments: page,
+                    cursor: if index < len { Some(index) } else { None },
+                }
+            }
+            CommentSort::Top => {
+                let top = self.top_comments.get(&post_id).unwrap_or_default();
+                let from_index = cursor.unwrap_or(0);
+                let next = from_index + limit;
+                CommentPage {
+                    cursor: if next < top.len() as u64 {
+                        Some(next)
+                    } else {
+                        None
+                    },
+                    comments: top
+                        .into_iter()
+                        .skip(from_index as usize)
+                        .take(limit as usize)
+                        .map(|(comment_id, _)| comments.get(comment_id).unwrap())
+                        .collect(),
+                }
+            }
+        }
+    }
+
+    pub fn get_comment(&self, post_id: PostId, comment_id: CommentId) -> Option<Comment> {
+        self.comments
+            .get(&post_id)
+            .and_then(|comments| comments.get(comment_id))
+    }
 
-        let from = if comments.len() > (limit + from_index) {
-            comments.len() - limit - from_index
-        } else {
-            0
+    /// Direct replies of a comment, oldest first.
+    pub fn get_comment_replies(
+        &self,
+        post_id: PostId,
+        comment_id: CommentId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<Comment> {
+        let comments = match self.comments.get(&post_id) {
+            Some(comments) => comments,
+            None => return vec![],
         };
 
-        let to = if comments.len() > from_index {
-            comments.len() - from_index
-        } else {
-            0
+        match self.comment_replies.get(&(post_id, comment_id)) {
+            Some(replies) => (from_index..std::cmp::min(from_index + limit, replies.len()))
+                .map(|index| comments.get(replies.get(index).unwrap()).unwrap())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// The comment followed by all of its descendants in depth-first order.
+    /// Deleted comments are returned as tombstones to keep the tree shape.
+    pub fn get_comment_thread(
+        &self,
+        post_id: PostId,
+        comment_id: CommentId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<Comment> {
+        let comments = match self.comments.get(&post_id) {
+            Some(comments) => comments,
+            None => return vec![],
         };
+        assert!(comment_id < comments.len(), "Comment not found");
+
+        let to_index = from_index + limit;
+        let mut result = vec![];
+        let mut visited = 0;
+        let mut stack = vec![comment_id];
+
+        while let Some(id) = stack.pop() {
+            if visited >= to_index {
+                break;
+            }
+            if visited >= from_index {
+                result.push(comments.get(id).unwrap());
+            }
+            visited += 1;
 
-        (from..to)
-            .map(|index| comments.get(index).unwrap())
-            .rev()
-            .collect()
+            if let Some(replies) = self.comment_replies.get(&(post_id.clone(), id)) {
+                stack.extend(replies.iter().collect::<Vec<CommentId>>().into_iter().rev());
+            }
+        }
+
+        result
     }
 
     pub fn get_num_post_comments(&self, post_id: PostId) -> u64 {
@@ -103,3 +355,170 @@ impl Contract {
             .len()
     }
 }
+
+impl Contract {
+    /// The post owner gains reputation for the first live comment of each other account, so
+    /// commenting again doesn't farm it.
+    pub(crate) fn internal_add_commenter(
+        &mut self,
+        post_id: &PostId,
+        post_owner: &AccountId,
+        commenter: &AccountId,
+    ) {
+        if post_owner == commenter {
+            return;
+        }
+        let key = (post_id.clone(), commenter.clone());
+        let num_comments = self.commenters.get(&key).unwrap_or(0);
+        self.commenters.insert(&key, &(num_comments + 1));
+        if num_comments == 0 {
+            self.internal_add_reputation(post_owner, COMMENT_REPUTATION);
+        }
+    }
+
+    /// Takes the reputation back with the last live comment of the commenter.
+    fn internal_remove_commenter(
+        &mut self,
+        post_id: &PostId,
+        post_owner: &AccountId,
+        commenter: &AccountId,
+    ) {
+        let key = (post_id.clone(), commenter.clone());
+        match self.commenters.get(&key) {
+            Some(1) => {
+                self.commenters.remove(&key);
+                self.internal_add_reputation(post_owner, -COMMENT_REPUTATION);
+            }
+            Some(num_comments) => {
+                self.commenters.insert(&key, &(num_comments - 1));
+            }
+            None => {}
+        }
+    }
+
+    /// Like `internal_rank_post`, a comment that fell out of the list only comes back when it
+    /// gets a new vote.
+    pub(crate) fn internal_rank_comment(&mut self, post_id: &PostId, comment: &Comment) {
+        let mut top = self.top_comments.get(post_id).unwrap_or_default();
+        top.retain(|(comment_id, _)| *comment_id != comment.id);
+        let position = top
+            .iter()
+            .position(|(comment_id, score)| (*score, *comment_id) < (comment.score, comment.id))
+            .unwrap_or(top.len());
+        if position < MAX_TOP_COMMENTS {
+            top.insert(position, (comment.id, comment.score));
+            top.truncate(MAX_TOP_COMMENTS);
+        }
+        self.top_comments.insert(post_id, &top);
+    }
+
+    pub(crate) fn internal_unrank_comment(&mut self, post_id: &PostId, comment_id: CommentId) {
+        if let Some(mut top) = self.top_comments.get(post_id) {
+            top.retain(|(id, _)| *id != comment_id);
+            self.top_comments.insert(post_id, &top);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn ids(comments: Vec<Comment>) -> Vec<CommentId> {
+        comments.into_iter().map(|comment| comment.id).collect()
+    }
+
+    #[test]
+    fn test_comment_threads() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(2, NOW).build());
+        let root = contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(3, NOW).build());
+        let reply = contract.comment(post.id.clone(), "body".to_string(), Some(root.id));
+        testing_env!(context(2, NOW).build());
+        contract.comment(post.id.clone(), "body".to_string(), Some(reply.id));
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.comment(post.id.clone(), "body".to_string(), Some(root.id));
+
+        assert_eq!(
+            ids(contract.get_comment_replies(post.id.clone(), root.id, 0, 10)),
+            vec![1, 4]
+        );
+        assert_eq!(
+            ids(contract.get_comment_thread(post.id.clone(), root.id, 0, 10)),
+            vec![0, 1, 2, 4]
+        );
+        assert_eq!(
+            ids(contract.get_comment_thread(post.id.clone(), root.id, 1, 2)),
+            vec![1, 2]
+        );
+        assert_eq!(contract.get_num_post_comments(post.id), 5);
+    }
+
+    #[test]
+    fn test_deleted_comments_are_skipped_and_refunded() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        for _ in 0..5 {
+            contract.comment(post.id.clone(), IPFS_BODY.to_string(), None);
+        }
+
+        let used_bytes = |contract: &Contract, index| {
+            let account_id: AccountId = accounts(index).into();
+            contract
+                .storage_accounts
+                .get(&account_id)
+                .unwrap()
+                .used_bytes
+        };
+        let author_bytes = used_bytes(&contract, 1);
+        let owner_bytes = used_bytes(&contract, 2);
+        testing_env!(context(1, NOW).build());
+        contract.delete_comment(post.id.clone(), 1);
+        contract.delete_comment(post.id.clone(), 3);
+        assert_eq!(used_bytes(&contract, 1), author_bytes);
+        assert!(used_bytes(&contract, 2) < owner_bytes);
+
+        let tombstone = contract.get_comment(post.id.clone(), 1).unwrap();
+        assert!(tombstone.deleted);
+        assert!(tombstone.body.is_empty());
+
+        let first = contract.get_comments(post.id.clone(), None, 2, None);
+        assert_eq!(ids(first.comments), vec![4, 2]);
+        let second = contract.get_comments(post.id.clone(), first.cursor, 2, None);
+        assert_eq!(ids(second.comments), vec![0]);
+        assert!(second.cursor.is_none());
+
+        let oldest = Some(CommentSort::Oldest);
+        let first = contract.get_comments(post.id.clone(), None, 2, oldest);
+        assert_eq!(ids(first.comments), vec![0, 2]);
+        let second = contract.get_comments(post.id, first.cursor, 2, oldest);
+        assert_eq!(ids(second.comments), vec![4]);
+        assert!(second.cursor.is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Post not found")]
+    fn test_comment_on_deleted_post() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.delete_post(post.id.clone());
+        testing_env!(context(2, NOW).build());
+        contract.comment(post.id, "body".to_string(), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "You don't have permission to delete this comment")]
+    fn test_only_owner_author_or_moderator_can_delete() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(3, NOW).build());
+        contract.delete_comment(post.id, 0);
+    }
+}
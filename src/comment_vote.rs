@@ -0,0 +1,243 @@
+use super::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Each upvote adds one to the comment score and to the reputation of its author.
+    pub fn upvote_comment(&mut self, post_id: PostId, comment_id: CommentId) {
+        let account_id = env::predecessor_account_id();
+        let comment = self.internal_get_live_comment(&post_id, comment_id);
+        assert!(
+            comment.owner != account_id,
+            "Can't vote for your own comment"
+        );
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let key = (post_id.clone(), comment_id);
+        let mut votes = self.comment_votes.get(&key).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::CommentVotesInner {
+                post_id: post_id.clone(),
+                comment_id,
+            })
+        });
+        assert!(votes.insert(&account_id), "You already upvote!");
+        self.comment_votes.insert(&key, &votes);
+
+        let comment = self.internal_update_comment_score(&post_id, comment, 1);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommentVote(CommentData {
+            post_id: &post_id,
+            comment_id,
+            parent_id: comment.parent_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    /// Also works on deleted comments, so voters can reclaim their storage.
+    pub fn unvote_comment(&mut self, post_id: PostId, comment_id: CommentId) {
+        let account_id = env::predecessor_account_id();
+        let comment = self
+            .get_comment(post_id.clone(), comment_id)
+            .expect("Comment not found");
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let key = (post_id.clone(), comment_id);
+        let mut votes = self
+            .comment_votes
+            .get(&key)
+            .expect("You didn't upvote this comment");
+        assert!(votes.remove(&account_id), "You didn't upvote this comment");
+        self.comment_votes.insert(&key, &votes);
+
+        let comment = self.internal_update_comment_score(&post_id, comment, -1);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommentUnvote(CommentData {
+            post_id: &post_id,
+            comment_id,
+            parent_id: comment.parent_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn comment_vote_status(
+        &self,
+        post_id: PostId,
+        comment_id: CommentId,
+        account_id: AccountId,
+    ) -> VoteStatus {
+        let upvoted = self
+            .comment_votes
+            .get(&(post_id, comment_id))
+            .map(|votes| votes.contains(&account_id))
+            .unwrap_or(false);
+        if upvoted {
+            VoteStatus::UpVote
+        } else {
+            VoteStatus::Default
+        }
+    }
+}
+
+impl Contract {
+    fn internal_get_live_comment(&self, post_id: &PostId, comment_id: CommentId) -> Comment {
+        let comment = self
+            .comments
+            .get(post_id)
+            .and_then(|comments| comments.get(comment_id))
+            .expect("Comment not found");
+        assert!(!comment.deleted, "Comment was deleted");
+        comment
+    }
+
+    fn internal_update_comment_score(
+        &mut self,
+        post_id: &PostId,
+        mut comment: Comment,
+        delta: i64,
+    ) -> Comment {
+        let mut comments = self.comments.get(post_id).unwrap();
+        comment.score += delta;
+        comments.replace(comment.id, &comment);
+        self.comments.insert(post_id, &comments);
+        if !comment.deleted {
+            self.internal_rank_comment(post_id, &comment);
+        }
+
+        self.internal_add_reputation(&comment.owner, delta * UPVOTE_REPUTATION);
+        comment
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn ids(comments: Vec<Comment>) -> Vec<CommentId> {
+        comments.into_iter().map(|comment| comment.id).collect()
+    }
+
+    #[test]
+    fn test_comment_votes_sort_and_reputation() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        for _ in 0..3 {
+            contract.comment(post.id.clone(), "body".to_string(), None);
+        }
+
+        testing_env!(context(2, NOW).build());
+        contract.upvote_comment(post.id.clone(), 1);
+        contract.upvote_comment(post.id.clone(), 0);
+        testing_env!(context(3, NOW).build());
+        contract.upvote_comment(post.id.clone(), 1);
+
+        let comments = |sort| {
+            contract
+                .get_comments(post.id.clone(), None, 10, Some(sort))
+                .comments
+        };
+        assert_eq!(ids(comments(CommentSort::Top)), vec![1, 0, 2]);
+        assert_eq!(ids(comments(CommentSort::Newest)), vec![2, 1, 0]);
+        assert_eq!(ids(comments(CommentSort::Oldest)), vec![0, 1, 2]);
+        assert_eq!(contract.get_account(accounts(1)).unwrap().reputation, 3);
+
+        contract.unvote_comment(post.id.clone(), 1);
+        assert_eq!(contract.get_comment(post.id.clone(), 1).unwrap().score, 1);
+        assert_eq!(contract.get_account(accounts(1)).unwrap().reputation, 2);
+    }
+
+    #[test]
+    fn test_top_comments_are_bounded() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        for _ in 0..MAX_TOP_COMMENTS + 1 {
+            // Resets the logs, the mocked blockchain keeps at most 100 of them.
+            testing_env!(context(1, NOW).build());
+            contract.comment(post.id.clone(), "body".to_string(), None);
+        }
+        testing_env!(context(2, NOW).build());
+        contract.upvote_comment(post.id.clone(), 0);
+
+        let top = contract.get_comments(post.id.clone(), None, 2, Some(CommentSort::Top));
+        assert_eq!(ids(top.comments), vec![0, MAX_TOP_COMMENTS as u64]);
+        assert_eq!(
+            contract.top_comments.get(&post.id).unwrap().len(),
+            MAX_TOP_COMMENTS
+        );
+    }
+
+    #[test]
+    fn test_deleted_comments_leave_top_and_can_be_unvoted() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+
+        testing_env!(context(2, NOW).build());
+        contract.upvote_comment(post.id.clone(), 0);
+        let used_bytes = |contract: &Contract| {
+            contract
+                .storage_accounts
+                .get(&accounts(2).into())
+                .unwrap()
+                .used_bytes
+        };
+        let voted_bytes = used_bytes(&contract);
+
+        testing_env!(context(1, NOW).build());
+        contract.delete_comment(post.id.clone(), 0);
+        let top = contract.get_comments(post.id.clone(), None, 10, Some(CommentSort::Top));
+        assert_eq!(ids(top.comments), vec![1]);
+
+        testing_env!(context(2, NOW).build());
+        contract.unvote_comment(post.id.clone(), 0);
+        assert!(used_bytes(&contract) < voted_bytes);
+        assert_eq!(contract.get_comment(post.id.clone(), 0).unwrap().score, 0);
+        let top = contract.get_comments(post.id, None, 10, Some(CommentSort::Top));
+        assert_eq!(ids(top.comments), vec![1]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Comment was deleted")]
+    fn test_deleted_comments_can_not_be_upvoted() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.delete_comment(post.id.clone(), 0);
+        testing_env!(context(2, NOW).build());
+        contract.upvote_comment(post.id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "You already upvote!")]
+    fn test_upvote_comment_once() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(2, NOW).build());
+        contract.upvote_comment(post.id.clone(), 0);
+        contract.upvote_comment(post.id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't vote for your own comment")]
+    fn test_upvote_own_comment() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.upvote_comment(post.id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "You didn't upvote this comment")]
+    fn test_unvote_comment_without_vote() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(2, NOW).build());
+        contract.unvote_comment(post.id, 0);
+    }
+}
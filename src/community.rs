@@ -11,6 +11,7 @@ pub struct WrappedCommunity {
     name: String,
     created_time: U64,
     description: String,
+    visibility: CommunityVisibility,
 
     //stats
     pub posts_count: u16,
@@ -26,6 +27,7 @@ impl From<Community> for WrappedCommunity {
             name: community.name,
             created_time: community.created_time,
             description: community.description,
+            visibility: community.visibility,
 
             //stats
             posts_count: 0,
@@ -35,13 +37,29 @@ impl From<Community> for WrappedCommunity {
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct Community {
-    id: CommunityId,
-    admin: ValidAccountId,
-    thumbnail: String,
-    avatar: String,
-    name: String,
-    created_time: U64,
-    description: String,
+    pub id: CommunityId,
+    pub admin: ValidAccountId,
+    pub thumbnail: String,
+    pub avatar: String,
+    pub name: String,
+    pub created_time: U64,
+    pub description: String,
+    pub visibility: CommunityVisibility,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "type")]
+/// Only restricts who can join, post, comment and vote. Like every contract state, the posts
+/// of a non-public community can be read by anyone, views can't tell who is calling them.
+/// Clients that need private content have to encrypt it off-chain.
+pub enum CommunityVisibility {
+    /// Anyone can join.
+    Public,
+    /// Accounts ask to join and a moderator approves them.
+    RequestToJoin,
+    /// Only accounts invited by a moderator can join.
+    InviteOnly,
 }
 
 pub type CommunityId = String;
@@ -55,6 +73,7 @@ impl Contract {
         avatar: Option<String>,
         name: String,
         description: String,
+        visibility: Option<CommunityVisibility>,
     ) -> CommunityId {
         let community_id = name.to_lowercase().replace(' ', "_");
 
@@ -92,6 +111,7 @@ impl Contract {
             // thumbnail: topic_thumbnail,
             created_time: env::block_timestamp().into(),
             description,
+            visibility: visibility.unwrap_or(CommunityVisibility::Public),
         };
 
         let mut members = UnorderedSet::new(StorageKey::MemberInCommunitesInner {
@@ -101,16 +121,18 @@ impl Contract {
         self.members_in_communites.insert(&community_id, &members);
 
         //add community Id
-        let mut user: Account = self
-            .accounts
-            .get(&account_id)
-            .expect("User not found")
-            .into();
+        let mut user = self.internal_get_account(&account_id);
         user.joined_communities.insert(&community_id);
-        self.accounts.insert(&account_id, &user.into());
+        self.internal_set_account(&account_id, user);
 
         self.communities.insert(&community_id.clone(), &community);
         self.finalize_storage_update(storage_update);
+
+        Event::CommunityCreate(CommunityData {
+            community_id: &community_id,
+            account_id: &account_id,
+        })
+        .emit();
         community_id
     }
 
@@ -124,33 +146,25 @@ impl Contract {
     }
 
     //Join a community / left a community
+    /// Public communities can be joined directly, other communities need an invite.
     pub fn join_community(&mut self, community_id: CommunityId) {
-        let mut members = self
-            .members_in_communites
-            .get(&community_id)
-            .expect("Not found your community");
-
-        // let new_member = ValidAccountId::try_from(env::predecessor_account_id()).unwrap();
-        assert!(
-            !members.contains(&env::predecessor_account_id()),
-            "You're already a member of this community"
-        );
-
-        //add community Id
-        let mut user: Account = self
-            .accounts
-            .get(&env::predecessor_account_id())
-            .expect("User not found")
-            .into();
-        user.joined_communities.insert(&community_id);
-        self.accounts
-            .insert(&env::predecessor_account_id(), &user.into());
+        self.assert_migrated();
+        let account_id = env::predecessor_account_id();
+        let community = self.internal_get_community(&community_id);
+        self.assert_not_blocked(community.admin.as_ref(), &account_id);
+
+        if !self.internal_take_community_invite(&community_id, &account_id) {
+            assert!(
+                community.visibility == CommunityVisibility::Public,
+                "This community requires an invite or an approved join request"
+            );
+        }
 
-        members.insert(&env::predecessor_account_id());
-        self.members_in_communites.insert(&community_id, &members);
+        self.internal_add_community_member(&community_id, &account_id);
     }
 
     pub fn leave_community(&mut self, community_id: CommunityId) {
+        self.assert_migrated();
         let community = self
             .communities
             .get(&community_id)
@@ -164,23 +178,27 @@ impl Contract {
         let new_member = ValidAccountId::try_from(env::predecessor_account_id()).unwrap();
         assert!(
             community.admin != new_member,
-            "Admin can not leave community"
+            "Admin can not leave community, transfer the admin rights first"
         );
         assert!(
             members.contains(&env::predecessor_account_id()),
             "You're not a member of this community"
         );
 
-        let mut user: Account = self
-            .accounts
-            .get(&new_member.clone().into())
-            .expect("User not found")
-            .into();
+        let new_member: AccountId = new_member.into();
+        let mut user = self.internal_get_account(&new_member);
         user.joined_communities.remove(&community_id);
-        self.accounts.insert(&new_member.into(), &user.into());
+        self.internal_set_account(&new_member, user);
 
         members.remove(&env::predecessor_account_id());
         self.members_in_communites.insert(&community_id, &members);
+        self.internal_remove_community_moderator(&community_id, &env::predecessor_account_id());
+
+        Event::CommunityLeave(CommunityData {
+            community_id: &community_id,
+            account_id: &env::predecessor_account_id(),
+        })
+        .emit();
     }
 
     pub fn already_joined(&self, community_id: CommunityId, account_id: ValidAccountId) -> bool {
@@ -197,10 +215,10 @@ impl Contract {
         title: String,
         body: String,
         post_type: PostType,
-        topic_id: TopicId,
+        topic_ids: Vec<TopicId>,
         community_id: CommunityId,
     ) -> Post {
-        assert!(self.topics.get(&topic_id).is_some(), "Not found your topic");
+        self.assert_post_topics(&topic_ids);
 
         assert!(
             title.len() <= MAX_TITLE_LENGTH,
@@ -223,10 +241,11 @@ impl Contract {
             members.contains(&env::predecessor_account_id()),
             "You're not a member of this community"
         );
+        self.assert_not_banned(&community_id, &env::predecessor_account_id());
+        let community = self.internal_get_community(&community_id);
+        self.assert_not_blocked(community.admin.as_ref(), &env::predecessor_account_id());
 
-        match post_type.clone() {
-            PostType::Website { url, site_id } => assert!(valid_url(url), "Not valid url")
-        };
+        post_type.assert_valid();
 
         let account_id = env::predecessor_account_id();
         let storage_update = self.new_storage_update(account_id.clone());
@@ -247,57 +266,38 @@ impl Contract {
             body,
             post_type,
             time: env::block_timestamp().into(),
-            topic: self.topics.get(&topic_id).unwrap(),
+            topics: topic_ids,
             num_quote: 0,
+            repost_of: None,
+            community_id: Some(community_id.clone()),
+            edited_at: None,
         };
 
-        let v_post = post.into();
-        let mut posts = self
-            .communities_posts
-            .get(&community_id)
-            .unwrap_or_else(|| {
-                UnorderedMap::new(StorageKey::CommunitiesPostsInner {
-                    id: community_id.clone() + &env::block_index().to_string(),
-                })
-            });
-
-        posts.insert(&post_id, &v_post);
-        self.communities_posts.insert(&community_id, &posts);
+        let post = self.internal_insert_post(post);
 
         // account.num_posts += 1;
         // account.last_post_height = block_height;
         self.internal_set_account(&account_id, account);
+        self.internal_notify_mentions(&post, "");
         self.finalize_storage_update(storage_update);
-        v_post.into()
+
+        Event::PostCreate(PostData {
+            post_id: &post_id,
+            account_id: &account_id,
+            community_id: Some(&community_id),
+        })
+        .emit();
+        post
     }
 
     pub fn delete_community_post(&mut self, post_id: PostId, community_id: CommunityId) {
-        let owner = env::predecessor_account_id();
-        let community = self
-            .communities
-            .get(&community_id)
-            .expect("Community not found");
-
-        let mut posts = self
-            .communities_posts
-            .get(&community_id)
-            .expect("Community's post not found");
-
-        let post: Post = posts.get(&post_id).expect("Post not found").into();
-
+        self.assert_migrated();
+        let post = self.internal_get_post(&post_id).expect("Post not found");
         assert!(
-            owner == post.account_id
-                || owner == community.admin.to_string()
-                || self.is_admin(owner),
-            "You don't have permission to delete"
+            post.community_id == Some(community_id),
+            "Community's post not found"
         );
-
-        //Delete this post
-        posts.remove(&post_id);
-        self.communities_posts.insert(&community_id, &posts);
-
-        //Add post id to list
-        self.deleted_posts.insert(&post_id);
+        self.delete_post(post_id);
     }
 
     pub fn get_deleted_posts(&self) -> Vec<PostId> {
@@ -313,8 +313,8 @@ impl Contract {
                 let mut community: WrappedCommunity = self.communities.get(&id).unwrap().into();
 
                 community.posts_count =
-                    if let Some(communites_posts) = self.communities_posts.get(&id) {
-                        communites_posts.len() as u16
+                    if let Some(community_posts) = self.community_posts.get(&id) {
+                        community_posts.len() as u16
                     } else {
                         0
                     };
@@ -336,48 +336,30 @@ impl Contract {
     pub fn get_community_posts(
         &self,
         community_id: CommunityId,
-        from_index: u64,
+        before: Option<U64>,
+        after: Option<U64>,
         limit: u64,
-    ) -> Vec<Post> {
-        let posts = self
-            .communities_posts
-            .get(&community_id)
-            .unwrap_or_else(|| {
-                UnorderedMap::new(StorageKey::CommunitiesPostsInner {
-                    id: community_id.clone() + &env::block_index().to_string(),
-                })
-            });
-
-        let keys = posts.keys_as_vector();
-
-        let from = if keys.len() > (limit + from_index) {
-            keys.len() - limit - from_index
-        } else {
-            0
+    ) -> PostPage {
+        let index = match self.community_post_index.get(&community_id) {
+            Some(index) => index,
+            None => {
+                return PostPage {
+                    posts: vec![],
+                    before: None,
+                    after: None,
+                }
+            }
         };
 
-        let to = if keys.len() > from_index {
-            keys.len() - from_index
-        } else {
-            0
-        };
-
-        (from..to)
-            .map(|index| {
-                let id = keys.get(index).expect("index of out bound");
-                posts.get(&id).unwrap().into()
-            })
-            .rev()
-            .collect()
+        page_post_index(&index, before, after, limit, |post_id| {
+            self.internal_get_post(post_id)
+        })
     }
 
     pub fn get_community_post_with_id(&self, community_id: CommunityId, post_id: PostId) -> Post {
-        let posts = self
-            .communities_posts
-            .get(&community_id)
-            .expect("Community not found");
-
-        posts.get(&post_id).expect("Post not found").into()
+        self.internal_get_post(&post_id)
+            .filter(|post| post.community_id == Some(community_id))
+            .expect("Post not found")
     }
 
     pub fn set_community_thumbnail(&mut self, thumbnail: String, community_id: CommunityId) {
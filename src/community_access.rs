@@ -0,0 +1,418 @@
+use super::*;
+
+#[near_bindgen]
+impl Contract {
+    pub fn set_community_visibility(
+        &mut self,
+        community_id: CommunityId,
+        visibility: CommunityVisibility,
+    ) {
+        let account_id = env::predecessor_account_id();
+        let mut community = self.internal_get_community(&community_id);
+        assert!(
+            account_id == community.admin.to_string(),
+            "You're not the admin of this community"
+        );
+
+        let storage_update = self.new_storage_update(account_id);
+        community.visibility = visibility;
+        self.communities.insert(&community_id, &community);
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn request_to_join(&mut self, community_id: CommunityId) {
+        let account_id = env::predecessor_account_id();
+        let community = self.internal_get_community(&community_id);
+        assert!(
+            community.visibility == CommunityVisibility::RequestToJoin,
+            "This community doesn't accept join requests"
+        );
+        assert!(
+            !self.already_joined(
+                community_id.clone(),
+                ValidAccountId::try_from(account_id.clone()).unwrap()
+            ),
+            "You're already a member of this community"
+        );
+        self.assert_not_banned(&community_id, &account_id);
+        self.assert_not_blocked(community.admin.as_ref(), &account_id);
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let mut requests = self
+            .community_join_requests
+            .get(&community_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::CommunityJoinRequestsInner {
+                    id: community_id.clone(),
+                })
+            });
+        assert!(
+            requests.insert(&account_id),
+            "You already requested to join"
+        );
+        self.community_join_requests
+            .insert(&community_id, &requests);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityJoinRequest(CommunityData {
+            community_id: &community_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn cancel_join_request(&mut self, community_id: CommunityId) {
+        let account_id = env::predecessor_account_id();
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        assert!(
+            self.internal_remove_join_request(&community_id, &account_id),
+            "Join request not found"
+        );
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn approve_join_request(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to approve join requests"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let account_id: AccountId = account_id.into();
+        assert!(
+            self.internal_remove_join_request(&community_id, &account_id),
+            "Join request not found"
+        );
+        self.internal_add_community_member(&community_id, &account_id);
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn reject_join_request(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to reject join requests"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let account_id: AccountId = account_id.into();
+        assert!(
+            self.internal_remove_join_request(&community_id, &account_id),
+            "Join request not found"
+        );
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityJoinReject(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    /// Invited account joins with `join_community`, whatever the community visibility.
+    pub fn invite_member(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to invite members"
+        );
+        assert!(
+            !self.already_joined(community_id.clone(), account_id.clone()),
+            "This account is already a member of this community"
+        );
+
+        let account_id: AccountId = account_id.into();
+        self.internal_get_account(&account_id);
+        self.assert_not_banned(&community_id, &account_id);
+        self.assert_not_blocked(&account_id, &caller_id);
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let mut invites = self
+            .community_invites
+            .get(&community_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::CommunityInvitesInner {
+                    id: community_id.clone(),
+                })
+            });
+        assert!(invites.insert(&account_id), "Already invited");
+        self.community_invites.insert(&community_id, &invites);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityInvite(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    pub fn revoke_invite(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to revoke invites"
+        );
+
+        let storage_update = self.new_storage_update(caller_id);
+        assert!(
+            self.internal_take_community_invite(&community_id, account_id.as_ref()),
+            "Invite not found"
+        );
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn get_join_requests(
+        &self,
+        community_id: CommunityId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        match self.community_join_requests.get(&community_id) {
+            Some(requests) => {
+                let requests = requests.as_vector();
+                (from_index..std::cmp::min(from_index + limit, requests.len()))
+                    .map(|index| requests.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    pub fn get_community_invites(
+        &self,
+        community_id: CommunityId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        match self.community_invites.get(&community_id) {
+            Some(invites) => {
+                let invites = invites.as_vector();
+                (from_index..std::cmp::min(from_index + limit, invites.len()))
+                    .map(|index| invites.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_add_community_member(
+        &mut self,
+        community_id: &CommunityId,
+        account_id: &AccountId,
+    ) {
+        let mut members = self
+            .members_in_communites
+            .get(community_id)
+            .expect("Not found your community");
+
+        assert!(
+            !members.contains(account_id),
+            "You're already a member of this community"
+        );
+        self.assert_not_banned(community_id, account_id);
+
+        //add community Id
+        let mut user = self.internal_get_account(account_id);
+        user.joined_communities.insert(community_id);
+        self.internal_set_account(account_id, user);
+
+        members.insert(account_id);
+        self.members_in_communites.insert(community_id, &members);
+
+        Event::CommunityJoin(CommunityData {
+            community_id,
+            account_id,
+        })
+        .emit();
+    }
+
+    /// Removes the pending invite of `account_id`, returns whether there was one.
+    pub(crate) fn internal_take_community_invite(
+        &mut self,
+        community_id: &CommunityId,
+        account_id: &AccountId,
+    ) -> bool {
+        match self.community_invites.get(community_id) {
+            Some(mut invites) => {
+                let removed = invites.remove(account_id);
+                self.community_invites.insert(community_id, &invites);
+                removed
+            }
+            None => false,
+        }
+    }
+
+    fn internal_remove_join_request(
+        &mut self,
+        community_id: &CommunityId,
+        account_id: &AccountId,
+    ) -> bool {
+        match self.community_join_requests.get(community_id) {
+            Some(mut requests) => {
+                let removed = requests.remove(account_id);
+                self.community_join_requests.insert(community_id, &requests);
+                removed
+            }
+            None => false,
+        }
+    }
+
+    /// Whether `account_id` can interact with the posts of the community. Only calls that
+    /// authenticate `account_id` as their caller can rely on it, views use it to decide what
+    /// to list, not to hide anything.
+    pub(crate) fn internal_can_access_community(
+        &self,
+        community_id: &CommunityId,
+        account_id: Option<&AccountId>,
+    ) -> bool {
+        let community = self.internal_get_community(community_id);
+        community.visibility == CommunityVisibility::Public
+            || account_id
+                .map(|account_id| {
+                    self.members_in_communites
+                        .get(community_id)
+                        .map(|members| members.contains(account_id))
+                        .unwrap_or(false)
+                })
+                .unwrap_or(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_request_to_join_and_approve() {
+        let mut contract = setup();
+        let community_id =
+            new_community(&mut contract, 0, "Rust", CommunityVisibility::RequestToJoin);
+        testing_env!(context(1, NOW).build());
+        contract.request_to_join(community_id.clone());
+        testing_env!(context(2, NOW).build());
+        contract.request_to_join(community_id.clone());
+        contract.cancel_join_request(community_id.clone());
+        assert_eq!(
+            contract.get_join_requests(community_id.clone(), 0, 10),
+            vec![AccountId::from(accounts(1))]
+        );
+
+        testing_env!(context(0, NOW).build());
+        contract.approve_join_request(community_id.clone(), accounts(1));
+        assert!(contract.already_joined(community_id.clone(), accounts(1)));
+        assert!(contract
+            .get_join_requests(community_id.clone(), 0, 10)
+            .is_empty());
+        new_community_post(&mut contract, 1, NOW, &community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Join request not found")]
+    fn test_rejected_join_request_can_not_be_approved() {
+        let mut contract = setup();
+        let community_id =
+            new_community(&mut contract, 0, "Rust", CommunityVisibility::RequestToJoin);
+        testing_env!(context(1, NOW).build());
+        contract.request_to_join(community_id.clone());
+        testing_env!(context(0, NOW).build());
+        contract.reject_join_request(community_id.clone(), accounts(1));
+        contract.approve_join_request(community_id, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "This community requires an invite or an approved join request")]
+    fn test_request_to_join_community_can_not_be_joined_directly() {
+        let mut contract = setup();
+        let community_id =
+            new_community(&mut contract, 0, "Rust", CommunityVisibility::RequestToJoin);
+        testing_env!(context(1, NOW).build());
+        contract.join_community(community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "This community doesn't accept join requests")]
+    fn test_invite_only_community_rejects_join_requests() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::InviteOnly);
+        testing_env!(context(1, NOW).build());
+        contract.request_to_join(community_id);
+    }
+
+    #[test]
+    fn test_invite_is_used_once() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::InviteOnly);
+        contract.invite_member(community_id.clone(), accounts(1));
+        contract.invite_member(community_id.clone(), accounts(2));
+        contract.revoke_invite(community_id.clone(), accounts(2));
+        assert_eq!(
+            contract.get_community_invites(community_id.clone(), 0, 10),
+            vec![AccountId::from(accounts(1))]
+        );
+
+        testing_env!(context(1, NOW).build());
+        contract.join_community(community_id.clone());
+        assert!(contract.already_joined(community_id.clone(), accounts(1)));
+        assert!(contract
+            .get_community_invites(community_id.clone(), 0, 10)
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "This community requires an invite or an approved join request")]
+    fn test_invite_is_taken_on_join() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::InviteOnly);
+        contract.invite_member(community_id.clone(), accounts(1));
+        testing_env!(context(1, NOW).build());
+        contract.join_community(community_id.clone());
+        contract.leave_community(community_id.clone());
+        contract.join_community(community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "You don't have permission to invite members")]
+    fn test_members_can_not_invite() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::Public);
+        testing_env!(context(1, NOW).build());
+        contract.join_community(community_id.clone());
+        contract.invite_member(community_id, accounts(2));
+    }
+
+    #[test]
+    fn test_non_public_community_posts_are_public_state() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::InviteOnly);
+        let post = new_community_post(&mut contract, 0, NOW, &community_id);
+
+        // Views can't authenticate their caller, they return the posts to anyone.
+        assert_eq!(
+            contract
+                .get_community_posts(community_id.clone(), None, None, 10)
+                .posts
+                .len(),
+            1
+        );
+        assert_eq!(contract.get_post_by_id(post.id.clone()).id, post.id);
+        assert!(!contract.internal_can_access_post(&post, Some(&accounts(1).into())));
+        assert!(contract.internal_can_access_post(&post, Some(&accounts(0).into())));
+    }
+
+    #[test]
+    #[should_panic(expected = "This community is private")]
+    fn test_non_members_can_not_comment_in_non_public_community() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::InviteOnly);
+        let post = new_community_post(&mut contract, 0, NOW, &community_id);
+        testing_env!(context(1, NOW).build());
+        contract.comment(post.id, IPFS_BODY.to_string(), None);
+    }
+}
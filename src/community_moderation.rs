@@ -0,0 +1,425 @@
+use super::*;
+
+#[near_bindgen]
+impl Contract {
+    pub fn transfer_community_admin(
+        &mut self,
+        community_id: CommunityId,
+        new_admin_id: ValidAccountId,
+    ) {
+        let account_id = env::predecessor_account_id();
+        let mut community = self.internal_get_community(&community_id);
+        assert!(
+            account_id == community.admin.to_string(),
+            "You're not the admin of this community"
+        );
+        assert!(
+            self.already_joined(community_id.clone(), new_admin_id.clone()),
+            "New admin must be a member of this community"
+        );
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let new_admin_id: AccountId = new_admin_id.into();
+        self.internal_remove_community_moderator(&community_id, &new_admin_id);
+        community.admin = ValidAccountId::try_from(new_admin_id.clone()).unwrap();
+        self.communities.insert(&community_id, &community);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityAdminTransfer(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &new_admin_id,
+            by: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn add_community_moderator(
+        &mut self,
+        community_id: CommunityId,
+        account_id: ValidAccountId,
+    ) {
+        let caller_id = env::predecessor_account_id();
+        let community = self.internal_get_community(&community_id);
+        assert!(
+            caller_id == community.admin.to_string(),
+            "You're not the admin of this community"
+        );
+        assert!(
+            self.already_joined(community_id.clone(), account_id.clone()),
+            "Moderator must be a member of this community"
+        );
+        assert!(
+            account_id != community.admin,
+            "Admin is already a moderator"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let account_id: AccountId = account_id.into();
+        let mut moderators = self
+            .community_moderators
+            .get(&community_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::CommunityModeratorsInner {
+                    id: community_id.clone(),
+                })
+            });
+        assert!(moderators.insert(&account_id), "Already a moderator");
+        self.community_moderators.insert(&community_id, &moderators);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityModeratorAdd(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    pub fn remove_community_moderator(
+        &mut self,
+        community_id: CommunityId,
+        account_id: ValidAccountId,
+    ) {
+        let caller_id = env::predecessor_account_id();
+        let community = self.internal_get_community(&community_id);
+        assert!(
+            caller_id == community.admin.to_string(),
+            "You're not the admin of this community"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let account_id: AccountId = account_id.into();
+        assert!(
+            self.internal_remove_community_moderator(&community_id, &account_id),
+            "Not a moderator"
+        );
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityModeratorRemove(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    /// Admin can ban anyone but itself, moderators can only ban regular members.
+    pub fn ban_member(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        let community = self.internal_get_community(&community_id);
+        let account_id: AccountId = account_id.into();
+
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to ban members"
+        );
+        assert!(
+            account_id != community.admin.to_string(),
+            "Admin can not be banned"
+        );
+        assert!(
+            caller_id == community.admin.to_string()
+                || !self.is_community_moderator(community_id.clone(), account_id.clone()),
+            "Only the admin can ban a moderator"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let mut banned = self
+            .banned_in_communities
+            .get(&community_id)
+            .unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::BannedInCommunitiesInner {
+                    id: community_id.clone(),
+                })
+            });
+        assert!(banned.insert(&account_id), "Already banned");
+        self.banned_in_communities.insert(&community_id, &banned);
+
+        self.internal_remove_community_moderator(&community_id, &account_id);
+
+        let mut members = self
+            .members_in_communites
+            .get(&community_id)
+            .expect("Not found your community");
+        if members.remove(&account_id) {
+            self.members_in_communites.insert(&community_id, &members);
+
+            if let Some(mut user) = self.internal_get_account_optional(&account_id) {
+                user.joined_communities.remove(&community_id);
+                self.internal_set_account(&account_id, user);
+            }
+        }
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityBan(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    pub fn unban_member(&mut self, community_id: CommunityId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            self.is_community_moderator(community_id.clone(), caller_id.clone()),
+            "You don't have permission to unban members"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let account_id: AccountId = account_id.into();
+        let mut banned = self
+            .banned_in_communities
+            .get(&community_id)
+            .expect("Not banned");
+        assert!(banned.remove(&account_id), "Not banned");
+        self.banned_in_communities.insert(&community_id, &banned);
+        self.finalize_storage_update(storage_update);
+
+        Event::CommunityUnban(CommunityRoleData {
+            community_id: &community_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    /// Community admin is always a moderator.
+    pub fn is_community_moderator(&self, community_id: CommunityId, account_id: AccountId) -> bool {
+        let community = self.internal_get_community(&community_id);
+        account_id == community.admin.to_string()
+            || self
+                .community_moderators
+                .get(&community_id)
+                .map(|moderators| moderators.contains(&account_id))
+                .unwrap_or(false)
+    }
+
+    pub fn is_banned(&self, community_id: CommunityId, account_id: AccountId) -> bool {
+        self.banned_in_communities
+            .get(&community_id)
+            .map(|banned| banned.contains(&account_id))
+            .unwrap_or(false)
+    }
+
+    pub fn get_community_moderators(
+        &self,
+        community_id: CommunityId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        match self.community_moderators.get(&community_id) {
+            Some(moderators) => {
+                let moderators = moderators.as_vector();
+                (from_index..std::cmp::min(from_index + limit, moderators.len()))
+                    .map(|index| moderators.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    pub fn get_banned_members(
+        &self,
+        community_id: CommunityId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        match self.banned_in_communities.get(&community_id) {
+            Some(banned) => {
+                let banned = banned.as_vector();
+                (from_index..std::cmp::min(from_index + limit, banned.len()))
+                    .map(|index| banned.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_community(&self, community_id: &CommunityId) -> Community {
+        self.communities
+            .get(community_id)
+            .expect("Community not found")
+    }
+
+    pub(crate) fn assert_not_banned(&self, community_id: &CommunityId, account_id: &AccountId) {
+        assert!(
+            !self.is_banned(community_id.clone(), account_id.clone()),
+            "You're banned from this community"
+        );
+    }
+
+    pub(crate) fn internal_remove_community_moderator(
+        &mut self,
+        community_id: &CommunityId,
+        account_id: &AccountId,
+    ) -> bool {
+        match self.community_moderators.get(community_id) {
+            Some(mut moderators) => {
+                let removed = moderators.remove(account_id);
+                self.community_moderators.insert(community_id, &moderators);
+                removed
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Public community of alice joined by bob and charlie.
+    fn setup_community() -> (Contract, CommunityId) {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::Public);
+        for index in 1..3 {
+            testing_env!(context(index, NOW).build());
+            contract.join_community(community_id.clone());
+        }
+        testing_env!(context(0, NOW).build());
+        (contract, community_id)
+    }
+
+    #[test]
+    fn test_transfer_community_admin() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        contract.transfer_community_admin(community_id.clone(), accounts(1));
+
+        let community = contract.internal_get_community(&community_id);
+        assert_eq!(community.admin, accounts(1));
+        // The new admin is no longer listed as a moderator, the old one is a plain member.
+        assert!(contract
+            .get_community_moderators(community_id.clone(), 0, 10)
+            .is_empty());
+        assert!(!contract.is_community_moderator(community_id.clone(), accounts(0).into()));
+
+        testing_env!(context(0, NOW).build());
+        contract.leave_community(community_id.clone());
+        assert!(!contract.already_joined(community_id, accounts(0)));
+    }
+
+    #[test]
+    #[should_panic(expected = "New admin must be a member of this community")]
+    fn test_transfer_community_admin_to_non_member() {
+        let (mut contract, community_id) = setup_community();
+        contract.transfer_community_admin(community_id, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're not the admin of this community")]
+    fn test_only_admin_can_transfer_community() {
+        let (mut contract, community_id) = setup_community();
+        testing_env!(context(1, NOW).build());
+        contract.transfer_community_admin(community_id, accounts(1));
+    }
+
+    #[test]
+    fn test_add_and_remove_community_moderators() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        assert!(contract.is_community_moderator(community_id.clone(), accounts(1).into()));
+        assert_eq!(
+            contract.get_community_moderators(community_id.clone(), 0, 10),
+            vec![AccountId::from(accounts(1))]
+        );
+
+        contract.remove_community_moderator(community_id.clone(), accounts(1));
+        assert!(!contract.is_community_moderator(community_id.clone(), accounts(1).into()));
+
+        // Leaving the community also drops the moderator rights.
+        contract.add_community_moderator(community_id.clone(), accounts(2));
+        testing_env!(context(2, NOW).build());
+        contract.leave_community(community_id.clone());
+        assert!(!contract.is_community_moderator(community_id, accounts(2).into()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Moderator must be a member of this community")]
+    fn test_moderators_must_be_members() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're not the admin of this community")]
+    fn test_moderators_can_not_add_moderators() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        testing_env!(context(1, NOW).build());
+        contract.add_community_moderator(community_id, accounts(2));
+    }
+
+    #[test]
+    fn test_ban_removes_membership_and_unban() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        testing_env!(context(1, NOW).build());
+        contract.ban_member(community_id.clone(), accounts(2));
+
+        assert!(contract.is_banned(community_id.clone(), accounts(2).into()));
+        assert!(!contract.already_joined(community_id.clone(), accounts(2)));
+        assert_eq!(
+            contract.get_banned_members(community_id.clone(), 0, 10),
+            vec![AccountId::from(accounts(2))]
+        );
+        let account = contract.internal_get_account(&accounts(2).into());
+        assert!(!account.joined_communities.contains(&community_id));
+
+        contract.unban_member(community_id.clone(), accounts(2));
+        assert!(!contract.is_banned(community_id.clone(), accounts(2).into()));
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id.clone());
+        assert!(contract.already_joined(community_id, accounts(2)));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're banned from this community")]
+    fn test_banned_member_can_not_rejoin() {
+        let (mut contract, community_id) = setup_community();
+        contract.ban_member(community_id.clone(), accounts(1));
+        testing_env!(context(1, NOW).build());
+        contract.join_community(community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "You're not a member of this community")]
+    fn test_banned_member_can_not_post() {
+        let (mut contract, community_id) = setup_community();
+        new_community_post(&mut contract, 1, NOW, &community_id);
+        testing_env!(context(0, NOW).build());
+        contract.ban_member(community_id.clone(), accounts(1));
+        new_community_post(&mut contract, 1, NOW + 1, &community_id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the admin can ban a moderator")]
+    fn test_moderators_can_not_ban_moderators() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        contract.add_community_moderator(community_id.clone(), accounts(2));
+        testing_env!(context(1, NOW).build());
+        contract.ban_member(community_id, accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "Admin can not be banned")]
+    fn test_admin_can_not_be_banned() {
+        let (mut contract, community_id) = setup_community();
+        contract.add_community_moderator(community_id.clone(), accounts(1));
+        testing_env!(context(1, NOW).build());
+        contract.ban_member(community_id, accounts(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "You don't have permission to ban members")]
+    fn test_members_can_not_ban() {
+        let (mut contract, community_id) = setup_community();
+        testing_env!(context(1, NOW).build());
+        contract.ban_member(community_id, accounts(2));
+    }
+}
@@ -1,11 +1,32 @@
 pub const MAX_TITLE_LENGTH: usize = 280;
 pub const MAX_BODY_LENGTH: usize = 10_000;
 pub const IPFS_HASH_LENGTH: usize = 46;
+pub const MAX_GALLERY_SIZE: usize = 10;
+pub const MAX_POLL_OPTIONS: usize = 10;
+pub const MAX_POLL_OPTION_LENGTH: usize = 100;
+pub const MAX_POST_TOPICS: usize = 5;
+pub const MAX_POST_REVISIONS: u64 = 20;
+pub const MAX_TOPIC_DEPTH: usize = 3;
+pub const MAX_TOPIC_CHILDREN: u64 = 10;
+pub const MAX_POST_TAGS: usize = 10;
+pub const MAX_HASHTAG_LENGTH: usize = 64;
+pub const MAX_NOTIFICATIONS: u64 = 100;
+pub const MAX_GROUP_MEMBERS: u64 = 50;
 pub const LONGEST_ACCOUNT_ID: &str =
     "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
 pub const MAX_ACCOUNT_ID_LENGTH: usize = 64;
 pub const ONE_DAY_UNIX_TIME: u64 = 86_400;
 pub const ONE_WEEK_UNIX_TIME: u64 = 604_800;
+pub const MAX_FEED_SCAN: u64 = 200;
+pub const MAX_FEED_SOURCES: usize = 50;
+pub const MAX_LEADERBOARD_SIZE: usize = 100;
+pub const MAX_TOP_USERS: usize = 100;
+pub const MAX_TOP_COMMENTS: usize = 100;
+pub const UPVOTE_REPUTATION: i64 = 1;
+pub const COMMENT_REPUTATION: i64 = 1;
+pub const FOLLOWER_REPUTATION: i64 = 2;
+pub const CHEST_MINT_REPUTATION: i64 = 5;
+pub const TIME_DECAY_SCALE: i64 = 1_000;
 
 pub const MAX_MESSAGE_LENGTH: usize = 140;
 pub const DEFAULT_EXPIRE_TIME: u64 = 172_800_000_000_000;
@@ -0,0 +1,484 @@
+use crate::*;
+use near_sdk::serde_json;
+
+pub const EVENT_STANDARD: &str = "dwix";
+/// Bumped whenever the payload of an event changes.
+pub const EVENT_STANDARD_VERSION: &str = "1.1.0";
+
+/// NEP-297 event log, emitted as `EVENT_JSON:{"standard":..,"version":..,"event":..,"data":..}`.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct EventLog<'a> {
+    pub standard: &'static str,
+    pub version: &'static str,
+    #[serde(flatten)]
+    pub event: Event<'a>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "event", content = "data")]
+#[serde(rename_all = "snake_case")]
+pub enum Event<'a> {
+    PostCreate(PostData<'a>),
+    PostEdit(PostData<'a>),
+    PostDelete(PostData<'a>),
+    Repost(RepostData<'a>),
+    RepostDelete(RepostData<'a>),
+    CommentCreate(CommentData<'a>),
+    CommentEdit(CommentData<'a>),
+    CommentDelete(CommentData<'a>),
+    CommentVote(CommentData<'a>),
+    CommentUnvote(CommentData<'a>),
+    Vote(VoteData<'a>),
+    PollVote(PollVoteData<'a>),
+    Unvote(VoteData<'a>),
+    Follow(FollowData<'a>),
+    Unfollow(FollowData<'a>),
+    TopicUpdate(TopicData<'a>),
+    TopicArchive(TopicData<'a>),
+    TopicUnarchive(TopicData<'a>),
+    TopicFollow(TopicData<'a>),
+    TopicUnfollow(TopicData<'a>),
+    CommunityCreate(CommunityData<'a>),
+    CommunityJoin(CommunityData<'a>),
+    CommunityLeave(CommunityData<'a>),
+    CommunityAdminTransfer(CommunityRoleData<'a>),
+    CommunityModeratorAdd(CommunityRoleData<'a>),
+    CommunityModeratorRemove(CommunityRoleData<'a>),
+    CommunityBan(CommunityRoleData<'a>),
+    CommunityUnban(CommunityRoleData<'a>),
+    CommunityJoinRequest(CommunityData<'a>),
+    CommunityJoinReject(CommunityRoleData<'a>),
+    CommunityInvite(CommunityRoleData<'a>),
+    MessageSend(MessageData<'a>),
+    MessageRead(MessageReadData<'a>),
+    GroupMemberAdd(GroupMemberData<'a>),
+    GroupMemberRemove(GroupMemberData<'a>),
+    GroupMessageSend(GroupMessageData<'a>),
+    ChestPlace(ChestData<'a>),
+    ChestEdit(ChestData<'a>),
+    ChestDelete(ChestData<'a>),
+    ChestMint(ChestMintData<'a>),
+    AdminAdd(AdminData<'a>),
+    AdminRemove(AdminData<'a>),
+    RoleGrant(RoleData<'a>),
+    RoleRevoke(RoleData<'a>),
+    OwnerPropose(OwnerData<'a>),
+    OwnerTransfer(OwnerData<'a>),
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostData<'a> {
+    pub post_id: &'a str,
+    pub account_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub community_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RepostData<'a> {
+    pub post_id: &'a str,
+    pub original_post_id: &'a str,
+    pub account_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommentData<'a> {
+    pub post_id: &'a str,
+    pub comment_id: CommentId,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<CommentId>,
+    pub account_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteData<'a> {
+    pub post_id: &'a str,
+    pub account_id: &'a str,
+    pub reaction: VoteStatus,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PollVoteData<'a> {
+    pub post_id: &'a str,
+    pub account_id: &'a str,
+    pub option_index: u32,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FollowData<'a> {
+    pub account_id: &'a str,
+    pub follower_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TopicData<'a> {
+    pub topic_id: &'a str,
+    pub account_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommunityData<'a> {
+    pub community_id: &'a str,
+    pub account_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CommunityRoleData<'a> {
+    pub community_id: &'a str,
+    pub account_id: &'a str,
+    pub by: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageData<'a> {
+    pub conversation_id: &'a str,
+    pub message_index: u64,
+    pub sender_id: &'a str,
+    pub receiver_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MessageReadData<'a> {
+    pub conversation_id: &'a str,
+    pub message_index: u64,
+    pub account_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupMemberData<'a> {
+    pub conversation_id: &'a str,
+    pub account_id: &'a str,
+    pub by: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupMessageData<'a> {
+    pub conversation_id: &'a str,
+    pub message_index: u64,
+    pub sender_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChestData<'a> {
+    pub chest_id: &'a str,
+    pub account_id: &'a str,
+    pub place_id: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ChestMintData<'a> {
+    pub chest_id: &'a str,
+    pub sender_id: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receiver_id: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AdminData<'a> {
+    pub account_id: &'a str,
+    pub by: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RoleData<'a> {
+    pub account_id: &'a str,
+    pub role: Role,
+    pub by: &'a str,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OwnerData<'a> {
+    pub owner_id: &'a str,
+    pub new_owner_id: &'a str,
+}
+
+impl Event<'_> {
+    pub fn emit(self) {
+        let log = EventLog {
+            standard: EVENT_STANDARD,
+            version: EVENT_STANDARD_VERSION,
+            event: self,
+        };
+        env::log(format!("EVENT_JSON:{}", serde_json::to_string(&log).unwrap()).as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+    use near_sdk::serde_json::{json, Value};
+    use near_sdk::test_utils::get_logs;
+
+    fn events() -> Vec<Value> {
+        get_logs()
+            .iter()
+            .filter_map(|log| log.strip_prefix("EVENT_JSON:"))
+            .map(|log| serde_json::from_str(log).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_post_events() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "post_create",
+                "data": { "post_id": post.id, "account_id": "bob" }
+            })]
+        );
+
+        testing_env!(context(1, NOW).build());
+        let edited = contract.edit_post(post.id.clone(), Some("edited".to_string()), None, None);
+        assert_eq!(edited.title, "edited");
+        assert!(edited.edited_at.is_some());
+        assert_eq!(
+            contract.get_post_revisions(post.id.clone(), 0, 1)[0].title,
+            "title"
+        );
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "post_edit",
+                "data": { "post_id": post.id, "account_id": "bob" }
+            })]
+        );
+
+        testing_env!(context(1, NOW).build());
+        contract.delete_post(post.id.clone());
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "post_delete",
+                "data": { "post_id": post.id, "account_id": "bob" }
+            })]
+        );
+    }
+
+    #[test]
+    fn test_repost_events() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(2, NOW).build());
+        let repost = contract.repost(post.id.clone(), None);
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "repost",
+                "data": {
+                    "post_id": repost.id,
+                    "original_post_id": post.id,
+                    "account_id": "charlie"
+                }
+            })]
+        );
+        assert_eq!(contract.get_post_by_id(post.id.clone()).num_quote, 1);
+
+        testing_env!(context(2, NOW).build());
+        contract.undo_repost(post.id.clone());
+        let events = events();
+        assert_eq!(events[0]["event"], "repost_delete");
+        assert_eq!(events[1]["event"], "post_delete");
+        assert_eq!(contract.get_post_by_id(post.id.clone()).num_quote, 0);
+        assert!(contract.can_repost("charlie".to_string(), post.id));
+    }
+
+    #[test]
+    fn test_comment_and_vote_events() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(2, NOW).build());
+        let comment = contract.comment(post.id.clone(), IPFS_BODY.to_string(), None);
+        let reply = contract.comment(post.id.clone(), IPFS_BODY.to_string(), Some(comment.id));
+        assert_eq!(
+            events(),
+            vec![
+                json!({
+                    "standard": "dwix",
+                    "version": "1.1.0",
+                    "event": "comment_create",
+                    "data": { "post_id": post.id, "comment_id": 0, "account_id": "charlie" }
+                }),
+                json!({
+                    "standard": "dwix",
+                    "version": "1.1.0",
+                    "event": "comment_create",
+                    "data": {
+                        "post_id": post.id,
+                        "comment_id": reply.id,
+                        "parent_id": 0,
+                        "account_id": "charlie"
+                    }
+                }),
+            ]
+        );
+
+        testing_env!(context(2, NOW).build());
+        contract.upvote(post.id.clone());
+        contract.unvote(post.id.clone());
+        let events = events();
+        assert_eq!(events[0]["event"], "vote");
+        assert_eq!(events[1]["event"], "unvote");
+        assert_eq!(
+            events[1]["data"],
+            json!({
+                "post_id": post.id,
+                "account_id": "charlie",
+                "reaction": { "type": "UpVote" }
+            })
+        );
+    }
+
+    #[test]
+    fn test_follow_and_community_events() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.follow(accounts(2));
+        contract.unfollow(accounts(2).into());
+        let follow_events = events();
+        assert_eq!(follow_events[0]["event"], "follow");
+        assert_eq!(
+            follow_events[0]["data"],
+            json!({ "account_id": "charlie", "follower_id": "bob" })
+        );
+        assert_eq!(follow_events[1]["event"], "unfollow");
+
+        testing_env!(context(1, NOW).build());
+        let community_id =
+            contract.new_community(None, None, "Rust Lang".to_string(), "".to_string(), None);
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id.clone());
+        contract.leave_community(community_id);
+        let community_events = events();
+        assert_eq!(community_events[0]["event"], "community_join");
+        assert_eq!(
+            community_events[0]["data"],
+            json!({ "community_id": "rust_lang", "account_id": "charlie" })
+        );
+        assert_eq!(community_events[1]["event"], "community_leave");
+    }
+
+    #[test]
+    fn test_topic_events() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.new_topic("Rust".to_string(), "".to_string(), None);
+        contract.update_topic(
+            "rust".to_string(),
+            None,
+            None,
+            Some("https://near.org/rust.png".to_string()),
+        );
+        contract.archive_topic("rust".to_string());
+        let topic_events = events();
+        assert_eq!(topic_events[0]["event"], "topic_update");
+        assert_eq!(
+            topic_events[0]["data"],
+            json!({ "topic_id": "rust", "account_id": "bob" })
+        );
+        assert_eq!(topic_events[1]["event"], "topic_archive");
+    }
+
+    #[test]
+    fn test_message_and_admin_events() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.new_message(
+            accounts(2).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "message_send",
+                "data": {
+                    "conversation_id": "charlie_bob",
+                    "message_index": 0,
+                    "sender_id": "bob",
+                    "receiver_id": "charlie"
+                }
+            })]
+        );
+
+        testing_env!(context(0, NOW).build());
+        contract.add_admin(accounts(1).into());
+        assert_eq!(
+            events(),
+            vec![json!({
+                "standard": "dwix",
+                "version": "1.1.0",
+                "event": "admin_add",
+                "data": { "account_id": "bob", "by": "alice" }
+            })]
+        );
+    }
+
+    #[test]
+    fn test_chest_events() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        let chest = contract.place_chest(
+            "bob".to_string(),
+            "code".to_string(),
+            "hello".to_string(),
+            None,
+            Location {
+                label: "hanoi".to_string(),
+                lat: 21.0,
+                lng: 105.8,
+            },
+            None,
+        );
+        contract.edit_chest(
+            chest.id.clone(),
+            Location {
+                label: "saigon".to_string(),
+                lat: 10.8,
+                lng: 106.6,
+            },
+        );
+        contract.delete_chest(chest.id.clone());
+        assert_eq!(
+            events()
+                .iter()
+                .map(|event| event["event"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>(),
+            vec!["chest_place", "chest_edit", "chest_delete"]
+        );
+    }
+}
@@ -6,20 +6,31 @@ const DEFAULT_GAS_FEE: Gas = 20_000_000_000_000;
 
 #[ext_contract(ext_self)]
 pub trait ExtContract {
-    fn on_minted_chest(&mut self, chest_id: ChestId) -> bool;
+    fn on_minted_chest(&mut self, chest_id: ChestId, receiver_id: Option<AccountId>) -> bool;
 }
 
 #[near_bindgen]
 impl Contract {
+    /// `receiver_id` is missing in the callbacks of mints started before it was passed.
     #[private]
-    pub fn on_minted_chest(&mut self, chest_id: ChestId) -> bool {
-        env::log(format!("promise_result_count = {}", env::promise_results_count()).as_bytes());
+    pub fn on_minted_chest(&mut self, chest_id: ChestId, receiver_id: Option<AccountId>) -> bool {
         match env::promise_result(0) {
             PromiseResult::Successful(_) => {
                 let mut chest = self.chests.get(&chest_id)
                     .expect("Chest not found when call back.");
                 chest.minted = true;
                 self.chests.insert(&chest_id, &chest);
+                // The sender's entry in the top users can grow the state.
+                let initial_storage = env::storage_usage();
+                self.internal_add_reputation(&chest.sender_account_id, CHEST_MINT_REPUTATION);
+                self.internal_charge_storage(&chest.sender_account_id, initial_storage);
+
+                Event::ChestMint(ChestMintData {
+                    chest_id: &chest_id,
+                    sender_id: &chest.sender_account_id,
+                    receiver_id: receiver_id.as_deref(),
+                })
+                .emit();
                 
                 // let place_id = chest.location.label;
                 // let mut chests_at_place = self.chests_per_place.get(&place_id)
@@ -37,7 +48,7 @@ impl Contract {
 #[near_bindgen]
 impl Contract {
     pub fn set_ft_contract (&mut self, ft_contract: AccountId) {
-        assert_eq!(env::predecessor_account_id(), env::current_account_id(), "You can't set new ft contract id");
+        self.assert_owner();
         self.ft_contract = ft_contract
     }
 
@@ -131,7 +142,7 @@ impl Contract {
                 b"nft_mint".to_vec(),
                 json!({
                     "token_id": token_id,
-                    "receiver_id": receiver_id,
+                    "receiver_id": receiver_id.clone(),
                     "token_metadata": {
                         "title": "Rep.run invitation",
                         "description": "You was invited to Rep.run by ".to_string() + &chest.sender_account_id,
@@ -147,6 +158,7 @@ impl Contract {
             )
             .then(ext_self::on_minted_chest(
                     chest_id,
+                    Some(receiver_id),
                     &env::current_account_id(),
                     0,
                     DEFAULT_GAS_FEE
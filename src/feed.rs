@@ -0,0 +1,539 @@
+use crate::*;
+
+/// One page of a feed, newest first.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostPage {
+    pub posts: Vec<Post>,
+    /// Pass as `before` to load older posts, `None` once the oldest post was reached.
+    pub before: Option<U64>,
+    /// Pass as `after` to load newer posts.
+    pub after: Option<U64>,
+}
+
+/// Position in a merged feed, posts are ordered by `(time, post_id)`.
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeedCursor {
+    pub time: U64,
+    pub post_id: PostId,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FeedPage {
+    pub posts: Vec<Post>,
+    /// Pass as `cursor` to load older posts, `None` once every source was read.
+    pub cursor: Option<FeedCursor>,
+}
+
+/// Entry of the chronological indexes, the time lets feeds seek without reading posts.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct PostIndexEntry {
+    pub post_id: PostId,
+    pub time: u64,
+}
+
+impl PostIndexEntry {
+    fn key(&self) -> (u64, &str) {
+        (self.time, &self.post_id)
+    }
+}
+
+/// Reads one index backwards, from the newest entry older than the cursor.
+struct FeedSource {
+    index: Vector<PostIndexEntry>,
+    position: u64,
+    head: Option<PostIndexEntry>,
+}
+
+impl FeedSource {
+    fn new(index: Vector<PostIndexEntry>, cursor: Option<&FeedCursor>) -> Self {
+        let position = match cursor {
+            Some(cursor) => {
+                let (mut low, mut high) = (0, index.len());
+                while low < high {
+                    let middle = (low + high) / 2;
+                    if index.get(middle).unwrap().time <= cursor.time.0 {
+                        low = middle + 1;
+                    } else {
+                        high = middle;
+                    }
+                }
+                low
+            }
+            None => index.len(),
+        };
+
+        let mut source = Self {
+            index,
+            position,
+            head: None,
+        };
+        source.load_head();
+        // Entries sharing the cursor time were returned already unless they sort before it.
+        if let Some(cursor) = cursor {
+            while source
+                .head
+                .as_ref()
+                .map(|head| head.key() >= (cursor.time.0, cursor.post_id.as_str()))
+                .unwrap_or(false)
+            {
+                source.advance();
+            }
+        }
+        source
+    }
+
+    fn load_head(&mut self) {
+        self.head = self
+            .position
+            .checked_sub(1)
+            .map(|position| self.index.get(position).unwrap());
+    }
+
+    fn advance(&mut self) -> Option<PostIndexEntry> {
+        let head = self.head.take();
+        if head.is_some() {
+            self.position -= 1;
+            self.load_head();
+        }
+        head
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Posts and reposts of `account_id`, the accounts it follows, its joined communities and
+    /// its followed topics, newest first. Deleted posts, posts of blocked or muted accounts and
+    /// posts of private communities it didn't join are left out.
+    /// Only the first `MAX_FEED_SOURCES` of these accounts, communities and topics are read,
+    /// in this order, with the account itself always first.
+    pub fn get_home_feed(
+        &self,
+        account_id: ValidAccountId,
+        cursor: Option<FeedCursor>,
+        limit: u64,
+    ) -> FeedPage {
+        let account_id: AccountId = account_id.into();
+        let account = self.internal_get_account(&account_id);
+
+        let account_indexes = std::iter::once(account_id.clone())
+            .chain(account.following.iter())
+            .map(|id| self.account_post_index.get(&id));
+        let community_indexes = account
+            .joined_communities
+            .iter()
+            .map(|community_id| self.community_post_index.get(&community_id));
+        let topic_indexes = account
+            .followed_topics
+            .iter()
+            .flat_map(|topic_id| self.internal_get_topic_tree(&topic_id))
+            .map(|topic_id| self.topic_post_index.get(&topic_id));
+        let sources = account_indexes
+            .chain(community_indexes)
+            .chain(topic_indexes)
+            .take(MAX_FEED_SOURCES)
+            .flatten()
+            .map(|index| FeedSource::new(index, cursor.as_ref()))
+            .collect();
+
+        merge_feed_sources(sources, cursor, limit, |post_id| {
+            self.internal_get_post(post_id)
+                .filter(|post| self.internal_is_visible_in_feed(&account_id, &account, post))
+        })
+    }
+
+    /// Posts of a topic and of its child topics, newest first, over the first
+    /// `MAX_FEED_SOURCES` topics of the tree. Posts of non-public communities are left out.
+    pub fn get_topic_feed(
+        &self,
+        topic_id: TopicId,
+        cursor: Option<FeedCursor>,
+        limit: u64,
+    ) -> FeedPage {
+        let sources = self
+            .internal_get_topic_tree(&topic_id)
+            .iter()
+            .take(MAX_FEED_SOURCES)
+            .filter_map(|topic_id| self.topic_post_index.get(topic_id))
+            .map(|index| FeedSource::new(index, cursor.as_ref()))
+            .collect();
+
+        merge_feed_sources(sources, cursor, limit, |post_id| {
+            self.internal_get_post(post_id)
+                .filter(|post| self.internal_can_access_post(post, None))
+        })
+    }
+}
+
+/// Merges chronological indexes newest first, from the entries older than `cursor`.
+/// At most `MAX_FEED_SCAN` entries are read per call, like `page_post_index`.
+fn merge_feed_sources<F>(
+    mut sources: Vec<FeedSource>,
+    cursor: Option<FeedCursor>,
+    limit: u64,
+    get_visible_post: F,
+) -> FeedPage
+where
+    F: Fn(&PostId) -> Option<Post>,
+{
+    let mut posts = vec![];
+    let mut last: Option<PostIndexEntry> = None;
+    let mut scanned = 0;
+    while (posts.len() as u64) < limit && scanned < MAX_FEED_SCAN {
+        let source = match sources
+            .iter_mut()
+            .filter(|source| source.head.is_some())
+            .max_by(|a, b| {
+                a.head
+                    .as_ref()
+                    .unwrap()
+                    .key()
+                    .cmp(&b.head.as_ref().unwrap().key())
+            }) {
+            Some(source) => source,
+            None => break,
+        };
+        let entry = source.advance().unwrap();
+        scanned += 1;
+
+        // The same post can be listed by several sources.
+        if last
+            .as_ref()
+            .map(|last| last.key() == entry.key())
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        if let Some(post) = get_visible_post(&entry.post_id) {
+            posts.push(post);
+        }
+        last = Some(entry);
+    }
+
+    let exhausted = sources.iter().all(|source| source.head.is_none());
+    FeedPage {
+        posts,
+        cursor: if exhausted {
+            None
+        } else {
+            last.map(|entry| FeedCursor {
+                time: entry.time.into(),
+                post_id: entry.post_id,
+            })
+            .or(cursor)
+        },
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_index_post(&mut self, post: &Post) {
+        // The migration indexes V1 posts that are not in creation order.
+        insert_sorted(&mut self.post_index, post);
+
+        let mut account_posts = self
+            .account_post_index
+            .get(&post.account_id)
+            .unwrap_or_else(|| {
+                Vector::new(StorageKey::AccountPostIndexInner {
+                    id: post.account_id.clone(),
+                })
+            });
+        insert_sorted(&mut account_posts, post);
+        self.account_post_index
+            .insert(&post.account_id, &account_posts);
+    }
+
+    pub(crate) fn internal_index_community_post(
+        &mut self,
+        community_id: &CommunityId,
+        post: &Post,
+    ) {
+        let mut community_posts =
+            self.community_post_index
+                .get(community_id)
+                .unwrap_or_else(|| {
+                    Vector::new(StorageKey::CommunityPostIndexInner {
+                        id: community_id.clone(),
+                    })
+                });
+        // Members posting in the same block share the time, their ids don't follow the calls.
+        insert_sorted(&mut community_posts, post);
+        self.community_post_index
+            .insert(community_id, &community_posts);
+    }
+
+    pub(crate) fn internal_index_topic_post(&mut self, topic_id: &TopicId, post: &Post) {
+        let mut topic_posts = self.topic_post_index.get(topic_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::TopicPostIndexInner {
+                id: topic_id.clone(),
+            })
+        });
+        insert_sorted(&mut topic_posts, post);
+        self.topic_post_index.insert(topic_id, &topic_posts);
+    }
+
+    fn internal_is_visible_in_feed(
+        &self,
+        account_id: &AccountId,
+        account: &Account,
+        post: &Post,
+    ) -> bool {
+        let hidden = |author_id: &AccountId| {
+            account.blocked.contains(author_id)
+                || account.muted.contains(author_id)
+                || self.internal_is_blocked(author_id, account_id)
+        };
+        if hidden(&post.account_id) || !self.internal_can_access_post(post, Some(account_id)) {
+            return false;
+        }
+
+        match &post.repost_of {
+            Some(original_post_id) => match self.internal_get_post(original_post_id) {
+                Some(original) => !hidden(&original.account_id),
+                None => false,
+            },
+            None => true,
+        }
+    }
+}
+
+/// Backfilled posts can arrive out of order, the new entry is moved back until the index is
+/// sorted again.
+pub(crate) fn insert_sorted(index: &mut Vector<PostIndexEntry>, post: &Post) {
+    let entry = PostIndexEntry {
+        post_id: post.id.clone(),
+        time: post.time.0,
+    };
+
+    let mut position = index.len();
+    index.push(&entry);
+    while position > 0 {
+        let previous = index.get(position - 1).unwrap();
+        if previous.key() <= entry.key() {
+            break;
+        }
+        index.replace(position, &previous);
+        position -= 1;
+    }
+    index.replace(position, &entry);
+}
+
+/// Walks a chronological index from a cursor, skipping tombstones of deleted posts.
+/// At most `MAX_FEED_SCAN` entries are read per call, the returned cursors always move
+/// past what was scanned so clients can keep paging through long runs of deleted posts.
+pub(crate) fn page_post_index<F>(
+    index: &Vector<PostIndexEntry>,
+    before: Option<U64>,
+    after: Option<U64>,
+    limit: u64,
+    get_post: F,
+) -> PostPage
+where
+    F: Fn(&PostId) -> Option<Post>,
+{
+    assert!(
+        before.is_none() || after.is_none(),
+        "Use either the before or the after cursor"
+    );
+
+    let len = index.len();
+    let positions: Box<dyn Iterator<Item = u64>> = match after {
+        Some(after) => Box::new(u64::from(after) + 1..len),
+        None => Box::new((0..before.map(u64::from).unwrap_or(len).min(len)).rev()),
+    };
+
+    let mut posts = vec![];
+    let mut lowest: Option<u64> = None;
+    let mut highest: Option<u64> = None;
+    for position in positions.take(MAX_FEED_SCAN as usize) {
+        if posts.len() as u64 >= limit {
+            break;
+        }
+        lowest = Some(lowest.map_or(position, |lowest| lowest.min(position)));
+        highest = Some(highest.map_or(position, |highest| highest.max(position)));
+
+        if let Some(post) = get_post(&index.get(position).unwrap().post_id) {
+            posts.push(post);
+        }
+    }
+
+    if after.is_some() {
+        posts.reverse();
+    }
+
+    let before_position = lowest.unwrap_or_else(|| after.map_or(0, |after| u64::from(after) + 1));
+    PostPage {
+        posts,
+        before: if before_position > 0 {
+            Some(before_position.into())
+        } else {
+            None
+        },
+        after: highest.or_else(|| after.map(u64::from)).map(Into::into),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn ids(posts: &[Post]) -> Vec<PostId> {
+        posts.iter().map(|post| post.id.clone()).collect()
+    }
+
+    #[test]
+    fn test_cursor_pagination_skips_deleted_posts() {
+        let mut contract = setup();
+        for seconds in 1_600_000_001..1_600_000_006 {
+            new_post(&mut contract, 1, seconds);
+        }
+        contract.delete_post("1600000004_bob".to_string());
+
+        let first = contract.get_posts(None, None, 2);
+        assert_eq!(ids(&first.posts), vec!["1600000005_bob", "1600000003_bob"]);
+
+        let second = contract.get_posts(first.before, None, 2);
+        assert_eq!(ids(&second.posts), vec!["1600000002_bob", "1600000001_bob"]);
+        assert!(second.before.is_none());
+
+        let newer = contract.get_posts(None, second.after, 10);
+        assert_eq!(ids(&newer.posts), vec!["1600000005_bob", "1600000003_bob"]);
+
+        let by_account = contract.get_posts_by_account(accounts(1), None, None, 10);
+        assert_eq!(by_account.posts.len(), 4);
+    }
+
+    #[test]
+    fn test_home_feed_merges_followed_accounts() {
+        let mut contract = setup();
+        testing_env!(context(1, 1_600_000_000).build());
+        contract.follow(accounts(2));
+        contract.follow(accounts(3));
+
+        new_post(&mut contract, 2, 1_600_000_001);
+        new_post(&mut contract, 1, 1_600_000_002);
+        new_post(&mut contract, 3, 1_600_000_003);
+        new_post(&mut contract, 2, 1_600_000_004);
+        new_post(&mut contract, 0, 1_600_000_005);
+
+        testing_env!(context(2, 1_600_000_006).build());
+        contract.repost("1600000003_danny".to_string(), None);
+
+        let first = contract.get_home_feed(accounts(1), None, 3);
+        assert_eq!(
+            ids(&first.posts),
+            vec![
+                "1600000006_charlie",
+                "1600000004_charlie",
+                "1600000003_danny"
+            ]
+        );
+
+        let second = contract.get_home_feed(accounts(1), first.cursor, 3);
+        assert_eq!(
+            ids(&second.posts),
+            vec!["1600000002_bob", "1600000001_charlie"]
+        );
+        assert!(second.cursor.is_none());
+
+        testing_env!(context(1, 1_600_000_007).build());
+        contract.mute_account(accounts(3));
+        let muted = contract.get_home_feed(accounts(1), None, 10);
+        assert_eq!(
+            ids(&muted.posts),
+            vec!["1600000004_charlie", "1600000002_bob", "1600000001_charlie"]
+        );
+    }
+
+    #[test]
+    fn test_home_feed_pages_same_time_community_posts() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::Public);
+        for index in 1..4 {
+            testing_env!(context(index, NOW).build());
+            contract.join_community(community_id.clone());
+        }
+        new_community_post(&mut contract, 3, NOW + 1, &community_id);
+        new_community_post(&mut contract, 2, NOW + 1, &community_id);
+
+        let first = contract.get_home_feed(accounts(1), None, 1);
+        let second = contract.get_home_feed(accounts(1), first.cursor, 1);
+        assert_eq!(ids(&first.posts), vec!["0_1600000001_danny"]);
+        assert_eq!(ids(&second.posts), vec!["0_1600000001_charlie"]);
+        assert_eq!(
+            ids(&contract
+                .get_community_posts(community_id, None, None, 10)
+                .posts),
+            vec!["0_1600000001_danny", "0_1600000001_charlie"]
+        );
+    }
+
+    #[test]
+    fn test_home_feed_sources_are_bounded() {
+        let mut contract = setup();
+        for index in 0..MAX_FEED_SOURCES {
+            let account_id = format!("author{}.near", index);
+            testing_env!(context(0, NOW).build());
+            contract.storage_deposit(Some(ValidAccountId::try_from(account_id.clone()).unwrap()));
+            testing_env!(context(0, NOW + index as u64)
+                .predecessor_account_id(ValidAccountId::try_from(account_id.clone()).unwrap())
+                .build());
+            contract.post(
+                "title".to_string(),
+                IPFS_BODY.to_string(),
+                PostType::Text,
+                vec!["default".to_string()],
+            );
+            testing_env!(context(1, NOW).build());
+            contract.follow(ValidAccountId::try_from(account_id).unwrap());
+        }
+
+        // Bob's own index comes first, the last followed account is past the bound.
+        let feed = contract.get_home_feed(accounts(1), None, MAX_FEED_SOURCES as u64);
+        assert_eq!(feed.posts.len(), MAX_FEED_SOURCES - 1);
+        assert!(feed
+            .posts
+            .iter()
+            .all(|post| post.account_id != format!("author{}.near", MAX_FEED_SOURCES - 1)));
+    }
+
+    #[test]
+    fn test_topic_feed_and_backfill() {
+        let mut contract = setup();
+        testing_env!(context(1, 1_600_000_000).build());
+        contract.new_topic("Rust".to_string(), "".to_string(), None);
+        for seconds in 1_600_000_001..1_600_000_004 {
+            new_post(&mut contract, 1, seconds);
+        }
+        testing_env!(context(2, 1_600_000_004).build());
+        contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["rust".to_string()],
+        );
+        testing_env!(context(1, 1_600_000_005).build());
+        contract.delete_post("1600000002_bob".to_string());
+
+        let expected = vec!["1600000003_bob", "1600000001_bob"];
+        let first = contract.get_topic_feed("default".to_string(), None, 1);
+        let second = contract.get_topic_feed("default".to_string(), first.cursor, 10);
+        assert_eq!(ids(&first.posts), vec!["1600000003_bob"]);
+        assert_eq!(ids(&second.posts), vec!["1600000001_bob"]);
+        assert_eq!(contract.get_num_posts_of_topic("default".to_string()), 2);
+        assert_eq!(contract.get_num_posts_of_topic("rust".to_string()), 1);
+
+        testing_env!(context(0, 1_600_000_006).build());
+        let mut progress = contract.backfill_topic_posts(TopicBackfillStep::Clear, 0, 2);
+        while let Some(TopicBackfillProgress { step, next_index }) = progress {
+            progress = contract.backfill_topic_posts(step, next_index, 2);
+        }
+        let rebuilt = contract.get_topic_feed("default".to_string(), None, 10);
+        assert_eq!(ids(&rebuilt.posts), expected);
+        assert_eq!(contract.get_num_posts_of_topic("default".to_string()), 2);
+    }
+}
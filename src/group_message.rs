@@ -0,0 +1,339 @@
+use super::*;
+use std::collections::HashMap;
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupConversation {
+    pub id: MessageId,
+    pub creator: AccountId,
+    pub name: String,
+    pub created_time: U64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct GroupMessage {
+    pub message_id: MessageId,
+    pub sender_id: AccountId,
+    /// Ciphertext for every member, keyed by the member's account ID. Each body is
+    /// encrypted with the `message_pub_key` of its member.
+    pub bodies: HashMap<AccountId, String>,
+    pub time: u64,
+    pub block_height: BlockHeight,
+}
+
+#[near_bindgen]
+impl Contract {
+    pub fn new_group_conversation(
+        &mut self,
+        name: String,
+        members: Vec<ValidAccountId>,
+    ) -> MessageId {
+        assert!(
+            name.len() <= MAX_TITLE_LENGTH,
+            "Can not make a group name more than {} characters",
+            MAX_TITLE_LENGTH
+        );
+
+        let creator = env::predecessor_account_id();
+        // `:` is not allowed in account IDs, so group IDs never collide with 1:1 conversations.
+        let id = format!("group:{}:{}", creator, env::block_timestamp());
+        assert!(
+            self.group_conversations.get(&id).is_none(),
+            "Group conversation already exists"
+        );
+
+        let storage_update = self.new_storage_update(creator.clone());
+        self.group_conversations.insert(
+            &id,
+            &GroupConversation {
+                id: id.clone(),
+                creator: creator.clone(),
+                name,
+                created_time: env::block_timestamp().into(),
+            },
+        );
+        self.group_members.insert(
+            &id,
+            &UnorderedSet::new(StorageKey::GroupMembersInner { id: id.clone() }),
+        );
+
+        self.internal_add_group_member(&id, &creator, &creator);
+        for account_id in members {
+            let account_id: AccountId = account_id.into();
+            if account_id != creator {
+                self.internal_add_group_member(&id, &account_id, &creator);
+            }
+        }
+        self.finalize_storage_update(storage_update);
+
+        id
+    }
+
+    pub fn add_group_member(&mut self, conversation_id: MessageId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        let group = self.internal_get_group_conversation(&conversation_id);
+        assert!(
+            caller_id == group.creator,
+            "You're not the creator of this group"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        self.internal_add_group_member(&conversation_id, account_id.as_ref(), &caller_id);
+        self.finalize_storage_update(storage_update);
+    }
+
+    /// Creator can remove any other member, members can remove themselves.
+    pub fn remove_group_member(&mut self, conversation_id: MessageId, account_id: ValidAccountId) {
+        let caller_id = env::predecessor_account_id();
+        let group = self.internal_get_group_conversation(&conversation_id);
+        let account_id: AccountId = account_id.into();
+        assert!(
+            caller_id == group.creator || caller_id == account_id,
+            "You're not the creator of this group"
+        );
+        assert!(
+            account_id != group.creator,
+            "Creator can not be removed from the group"
+        );
+
+        let storage_update = self.new_storage_update(caller_id.clone());
+        let mut members = self.group_members.get(&conversation_id).unwrap();
+        assert!(members.remove(&account_id), "Not a member of this group");
+        self.group_members.insert(&conversation_id, &members);
+
+        if let Some(mut account) = self.internal_get_account_optional(&account_id) {
+            account.related_conversations.remove(&conversation_id);
+            self.internal_set_account(&account_id, account);
+        }
+        if let Some(mut receipts) = self.read_receipts.get(&conversation_id) {
+            receipts.remove(&account_id);
+            self.read_receipts.insert(&conversation_id, &receipts);
+        }
+        self.finalize_storage_update(storage_update);
+
+        Event::GroupMemberRemove(GroupMemberData {
+            conversation_id: &conversation_id,
+            account_id: &account_id,
+            by: &caller_id,
+        })
+        .emit();
+    }
+
+    /// `bodies` must hold exactly one ciphertext per member.
+    pub fn new_group_message(
+        &mut self,
+        conversation_id: MessageId,
+        bodies: HashMap<AccountId, String>,
+    ) {
+        let sender_id = env::predecessor_account_id();
+        self.internal_get_group_conversation(&conversation_id);
+        let members = self.group_members.get(&conversation_id).unwrap();
+        assert!(
+            members.contains(&sender_id),
+            "You're not a member of this group"
+        );
+
+        assert!(
+            bodies.len() as u64 == members.len()
+                && bodies.keys().all(|account_id| members.contains(account_id)),
+            "Message must be encrypted for every member of this group"
+        );
+
+        let storage_update = self.new_storage_update(sender_id.clone());
+        let mut history = self
+            .group_messages
+            .get(&conversation_id)
+            .unwrap_or_else(|| {
+                Vector::new(StorageKey::GroupMessagesInner {
+                    id: conversation_id.clone(),
+                })
+            });
+        history.push(&GroupMessage {
+            message_id: conversation_id.clone(),
+            sender_id: sender_id.clone(),
+            bodies,
+            time: env::block_timestamp(),
+            block_height: env::block_index(),
+        });
+        self.group_messages.insert(&conversation_id, &history);
+
+        let message_index = history.len() - 1;
+        self.internal_set_read_receipt(&conversation_id, &sender_id, message_index);
+        self.finalize_storage_update(storage_update);
+
+        Event::GroupMessageSend(GroupMessageData {
+            conversation_id: &conversation_id,
+            message_index,
+            sender_id: &sender_id,
+        })
+        .emit();
+    }
+
+    pub fn get_group_conversation(&self, conversation_id: MessageId) -> Option<GroupConversation> {
+        self.group_conversations.get(&conversation_id)
+    }
+
+    pub fn get_group_members(
+        &self,
+        conversation_id: MessageId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<AccountId> {
+        match self.group_members.get(&conversation_id) {
+            Some(members) => {
+                let members = members.as_vector();
+                (from_index..std::cmp::min(from_index + limit, members.len()))
+                    .map(|index| members.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Messages of the group, newest first.
+    pub fn get_group_messages(
+        &self,
+        conversation_id: MessageId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<GroupMessage> {
+        match self.group_messages.get(&conversation_id) {
+            Some(history) => calculate_rev_limit(history.len(), from_index, limit)
+                .map(|index| history.get(index).unwrap())
+                .rev()
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+impl Contract {
+    fn internal_get_group_conversation(&self, conversation_id: &MessageId) -> GroupConversation {
+        self.group_conversations
+            .get(conversation_id)
+            .expect("Group conversation not found")
+    }
+
+    fn internal_add_group_member(
+        &mut self,
+        conversation_id: &MessageId,
+        account_id: &AccountId,
+        by: &AccountId,
+    ) {
+        self.assert_not_blocked(account_id, by);
+        let mut account = self.internal_get_account(account_id);
+        let mut members = self.group_members.get(conversation_id).unwrap();
+        assert!(
+            members.len() < MAX_GROUP_MEMBERS,
+            "A group can not have more than {} members",
+            MAX_GROUP_MEMBERS
+        );
+        assert!(members.insert(account_id), "Already a member of this group");
+        self.group_members.insert(conversation_id, &members);
+
+        account.related_conversations.insert(conversation_id);
+        self.internal_set_account(account_id, account);
+
+        Event::GroupMemberAdd(GroupMemberData {
+            conversation_id,
+            account_id,
+            by,
+        })
+        .emit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Group of bob with charlie and danny.
+    fn setup_group() -> (Contract, MessageId) {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        let id =
+            contract.new_group_conversation("friends".to_string(), vec![accounts(2), accounts(3)]);
+        (contract, id)
+    }
+
+    fn bodies(member_ids: &[usize]) -> HashMap<AccountId, String> {
+        member_ids
+            .iter()
+            .map(|index| (accounts(*index).into(), format!("body for {}", index)))
+            .collect()
+    }
+
+    #[test]
+    fn test_group_messages() {
+        let (mut contract, id) = setup_group();
+        assert_eq!(contract.get_group_members(id.clone(), 0, 10).len(), 3);
+
+        contract.new_group_message(id.clone(), bodies(&[1, 2, 3]));
+        testing_env!(context(2, NOW + 1).build());
+        contract.new_group_message(id.clone(), bodies(&[1, 2, 3]));
+
+        let messages = contract.get_group_messages(id.clone(), 0, 10);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender_id, "charlie");
+        assert_eq!(
+            messages[0].bodies[&AccountId::from(accounts(3))],
+            "body for 3"
+        );
+        assert_eq!(contract.get_num_conversation_messages(id.clone()), 2);
+        assert_eq!(contract.get_unread_count(id, "danny".to_string()), 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "Message must be encrypted for every member of this group")]
+    fn test_group_message_needs_a_body_per_member() {
+        let (mut contract, id) = setup_group();
+        contract.new_group_message(id, bodies(&[1, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Message must be encrypted for every member of this group")]
+    fn test_group_message_rejects_bodies_of_non_members() {
+        let (mut contract, id) = setup_group();
+        contract.new_group_message(id, bodies(&[0, 1, 2]));
+    }
+
+    #[test]
+    fn test_removed_member_leaves_the_group() {
+        let (mut contract, id) = setup_group();
+        testing_env!(context(3, NOW).build());
+        contract.remove_group_member(id.clone(), accounts(3));
+        assert!(!contract
+            .internal_get_account(&accounts(3).into())
+            .related_conversations
+            .contains(&id));
+
+        testing_env!(context(1, NOW).build());
+        contract.new_group_message(id, bodies(&[1, 2]));
+    }
+
+    #[test]
+    #[should_panic(expected = "You're not the creator of this group")]
+    fn test_members_can_not_remove_others() {
+        let (mut contract, id) = setup_group();
+        testing_env!(context(2, NOW).build());
+        contract.remove_group_member(id, accounts(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "A group can not have more than 50 members")]
+    fn test_group_size_is_bounded() {
+        let mut contract = setup();
+        let members: Vec<ValidAccountId> = (0..MAX_GROUP_MEMBERS)
+            .map(|index| {
+                let account_id = ValidAccountId::try_from(format!("member{}.near", index)).unwrap();
+                testing_env!(context(0, NOW).build());
+                contract.storage_deposit(Some(account_id.clone()));
+                account_id
+            })
+            .collect();
+        testing_env!(context(1, NOW).build());
+        contract.new_group_conversation("crowd".to_string(), members);
+    }
+}
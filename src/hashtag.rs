@@ -0,0 +1,268 @@
+use crate::*;
+use once_cell::sync::Lazy;
+use regex::Regex;
+
+static HASHTAG_PATTERN: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?:^|[^\w#])#(\w+)").unwrap());
+static MENTION_PATTERN: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?:^|[^\w@.])@([a-z0-9_.\-]+)").unwrap());
+
+#[near_bindgen]
+impl Contract {
+    /// Posts whose title carries `#hashtag`, the last tagged first. Posts of non-public
+    /// communities are left out.
+    pub fn get_posts_by_hashtag(
+        &self,
+        hashtag: String,
+        before: Option<U64>,
+        after: Option<U64>,
+        limit: u64,
+    ) -> PostPage {
+        let hashtag = hashtag.trim_start_matches('#').to_lowercase();
+        let index = self.hashtag_post_index.get(&hashtag).unwrap_or_else(|| {
+            Vector::new(StorageKey::HashtagPostIndexInner {
+                hashtag: hashtag.clone(),
+            })
+        });
+        page_post_index(&index, before, after, limit, |post_id| {
+            self.internal_get_post(post_id).filter(|post| {
+                self.internal_can_access_post(post, None)
+                    && extract_hashtags(&post.title).contains(&hashtag)
+            })
+        })
+    }
+
+    /// Posts whose title mentions `@account_id`, the last mentioned first. Posts of
+    /// communities the account can't access are left out.
+    pub fn get_mentions(
+        &self,
+        account_id: ValidAccountId,
+        before: Option<U64>,
+        after: Option<U64>,
+        limit: u64,
+    ) -> PostPage {
+        let index = self
+            .mention_index
+            .get(account_id.as_ref())
+            .unwrap_or_else(|| {
+                Vector::new(StorageKey::MentionIndexInner {
+                    id: account_id.clone().into(),
+                })
+            });
+        page_post_index(&index, before, after, limit, |post_id| {
+            self.internal_get_post(post_id).filter(|post| {
+                self.internal_can_access_post(post, Some(account_id.as_ref()))
+                    && extract_mentions(&post.title).contains(account_id.as_ref())
+            })
+        })
+    }
+}
+
+impl Contract {
+    /// Appends the post to the indexes of the hashtags and mentions of the title that
+    /// `previous_title` didn't have, unless an earlier title already listed it there. Entries
+    /// are never removed, views check the current title instead.
+    pub(crate) fn internal_index_post_tags(&mut self, post: &Post, previous_title: &str) {
+        let previous_hashtags = extract_hashtags(previous_title);
+        for hashtag in extract_hashtags(&post.title) {
+            if previous_hashtags.contains(&hashtag)
+                || !self
+                    .hashtag_posts
+                    .insert(&(hashtag.clone(), post.id.clone()))
+            {
+                continue;
+            }
+            let mut index = self.hashtag_post_index.get(&hashtag).unwrap_or_else(|| {
+                Vector::new(StorageKey::HashtagPostIndexInner {
+                    hashtag: hashtag.clone(),
+                })
+            });
+            index.push(&PostIndexEntry {
+                post_id: post.id.clone(),
+                time: post.time.0,
+            });
+            self.hashtag_post_index.insert(&hashtag, &index);
+        }
+
+        for account_id in self.internal_new_mentions(post, previous_title) {
+            if !self
+                .mention_posts
+                .insert(&(account_id.clone(), post.id.clone()))
+            {
+                continue;
+            }
+            let mut index = self.mention_index.get(&account_id).unwrap_or_else(|| {
+                Vector::new(StorageKey::MentionIndexInner {
+                    id: account_id.clone(),
+                })
+            });
+            index.push(&PostIndexEntry {
+                post_id: post.id.clone(),
+                time: post.time.0,
+            });
+            self.mention_index.insert(&account_id, &index);
+        }
+    }
+
+    /// Registered accounts mentioned by the title but not by `previous_title`, leaving out
+    /// the author and the accounts that blocked them.
+    pub(crate) fn internal_new_mentions(
+        &self,
+        post: &Post,
+        previous_title: &str,
+    ) -> Vec<AccountId> {
+        let previous_mentions = extract_mentions(previous_title);
+        extract_mentions(&post.title)
+            .into_iter()
+            .filter(|account_id| {
+                !previous_mentions.contains(account_id)
+                    && account_id != &post.account_id
+                    && self.internal_get_account_optional(account_id).is_some()
+                    && !self.internal_is_blocked(account_id, &post.account_id)
+            })
+            .collect()
+    }
+}
+
+/// Lowercased `#hashtags` of `text`, without duplicates.
+pub fn extract_hashtags(text: &str) -> Vec<String> {
+    let mut hashtags: Vec<String> = vec![];
+    for capture in HASHTAG_PATTERN.captures_iter(text) {
+        let hashtag = capture[1].to_lowercase();
+        if hashtag.len() <= MAX_HASHTAG_LENGTH && !hashtags.contains(&hashtag) {
+            hashtags.push(hashtag);
+        }
+    }
+    hashtags.truncate(MAX_POST_TAGS);
+    hashtags
+}
+
+/// Valid account ids mentioned as `@account_id` in `text`, without duplicates.
+pub fn extract_mentions(text: &str) -> Vec<AccountId> {
+    let mut mentions: Vec<AccountId> = vec![];
+    for capture in MENTION_PATTERN.captures_iter(text) {
+        // A mention ending a sentence keeps the full stop.
+        let account_id = capture[1].trim_end_matches('.').to_string();
+        if ValidAccountId::try_from(account_id.clone()).is_ok() && !mentions.contains(&account_id) {
+            mentions.push(account_id);
+        }
+    }
+    mentions.truncate(MAX_POST_TAGS);
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn ids(page: PostPage) -> Vec<PostId> {
+        page.posts.into_iter().map(|post| post.id).collect()
+    }
+
+    #[test]
+    fn test_extract_tags() {
+        assert_eq!(
+            extract_hashtags("#Rust and #near, not a#tag or ##twice #rust"),
+            vec!["rust".to_string(), "near".to_string()]
+        );
+        assert_eq!(
+            extract_mentions("hi @bob.near, @charlie. and me@mail.com @Bob"),
+            vec!["bob.near".to_string(), "charlie".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_hashtag_and_mention_indexes() {
+        let mut contract = setup();
+
+        let mut new_post = |seconds: u64, title: &str| {
+            testing_env!(context(1, seconds).build());
+            contract
+                .post(
+                    title.to_string(),
+                    IPFS_BODY.to_string(),
+                    PostType::Text,
+                    vec!["default".to_string()],
+                )
+                .id
+        };
+        let first = new_post(1_600_000_001, "#Rust with @charlie");
+        let second = new_post(1_600_000_002, "more #rust");
+        new_post(1_600_000_003, "#near only");
+
+        assert_eq!(
+            ids(contract.get_posts_by_hashtag("#RUST".to_string(), None, None, 10)),
+            vec![second.clone(), first.clone()]
+        );
+        assert_eq!(
+            ids(contract.get_mentions(accounts(2), None, None, 10)),
+            vec![first.clone()]
+        );
+
+        testing_env!(context(1, 1_600_000_004).build());
+        contract.edit_post(
+            second.clone(),
+            Some("@charlie #near".to_string()),
+            None,
+            None,
+        );
+        assert_eq!(
+            ids(contract.get_posts_by_hashtag("rust".to_string(), None, None, 10)),
+            vec![first.clone()]
+        );
+        assert_eq!(
+            ids(contract.get_mentions(accounts(2), None, None, 10)),
+            vec![second, first]
+        );
+    }
+
+    #[test]
+    fn test_edits_append_tags_once() {
+        let mut contract = setup();
+        let first = new_post(&mut contract, 1, NOW + 1);
+        let second = new_post(&mut contract, 1, NOW + 2);
+        let edit_title = |contract: &mut Contract, post: &Post, seconds: u64, title: &str| {
+            testing_env!(context(1, seconds).build());
+            contract.edit_post(post.id.clone(), Some(title.to_string()), None, None);
+        };
+        edit_title(&mut contract, &second, NOW + 3, "#rust");
+        let page = contract.get_posts_by_hashtag("rust".to_string(), None, None, 1);
+
+        // The older post is tagged last, it is appended without moving the cursor.
+        edit_title(&mut contract, &first, NOW + 4, "#rust");
+        let newer = contract.get_posts_by_hashtag("rust".to_string(), None, page.after, 10);
+        assert_eq!(ids(newer), vec![first.id.clone()]);
+
+        edit_title(&mut contract, &first, NOW + 5, "no tag");
+        edit_title(&mut contract, &first, NOW + 6, "#rust again");
+        assert_eq!(
+            ids(contract.get_posts_by_hashtag("rust".to_string(), None, None, 10)),
+            vec![first.id, second.id]
+        );
+    }
+
+    #[test]
+    fn test_members_see_private_mentions() {
+        let mut contract = setup();
+        let community_id =
+            new_community(&mut contract, 1, "Rust", CommunityVisibility::RequestToJoin);
+        testing_env!(context(1, NOW).build());
+        contract.invite_member(community_id.clone(), accounts(2));
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id.clone());
+
+        testing_env!(context(1, NOW + 1).build());
+        let post = contract.community_post(
+            "hi @charlie and @danny".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["default".to_string()],
+            community_id,
+        );
+        assert_eq!(
+            ids(contract.get_mentions(accounts(2), None, None, 10)),
+            vec![post.id]
+        );
+        assert!(ids(contract.get_mentions(accounts(3), None, None, 10)).is_empty());
+    }
+}
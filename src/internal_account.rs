@@ -18,39 +18,38 @@ impl Contract {
     }
 
     pub(crate) fn internal_create_account(&mut self, account_id: &AccountId) -> Account {
-        let hash = env::sha256(account_id.as_bytes());
-        let mut following_key = vec![b'o'];
-        following_key.extend_from_slice(&hash);
-        let mut followers_key = vec![b'i'];
-        followers_key.extend(&hash);
-        let mut messages_key = vec![b'z'];
-        messages_key.extend(&hash);
-        let mut joined_communites_key = vec![b'j'];
-        joined_communites_key.extend(&hash);
-
         let account = Account {
-            following: UnorderedSet::new(following_key),
-            followers: UnorderedSet::new(followers_key),
+            following: UnorderedSet::new(account_prefix(b'o', account_id)),
+            followers: UnorderedSet::new(account_prefix(b'i', account_id)),
             chests: Vec::new(),
-            related_conversations: UnorderedSet::new(messages_key),
+            related_conversations: UnorderedSet::new(account_prefix(b'z', account_id)),
             message_pub_key: "".to_string(),
             avatar: "".to_string(),
             thumbnail: "".to_string(),
             bio: "".to_string(),
             display_name: "".to_string(),
-            joined_communities: UnorderedSet::new(joined_communites_key),
+            joined_communities: UnorderedSet::new(account_prefix(b'j', account_id)),
+            followed_topics: UnorderedSet::new(account_prefix(b't', account_id)),
             bookmarks: Vec::new(),
+            blocked: UnorderedSet::new(account_prefix(b'b', account_id)),
+            muted: UnorderedSet::new(account_prefix(b'm', account_id)),
+            reputation: 0,
+            num_notifications: 0,
+            notifications_read: 0,
+            notification_opt_outs: vec![],
         };
         let v_account = account.into();
         assert!(
             self.accounts.insert(account_id, &v_account).is_none(),
             "Account already exists"
         );
-        v_account.into()
+        v_account.into_account(account_id)
     }
 
     pub(crate) fn internal_get_account_optional(&self, account_id: &AccountId) -> Option<Account> {
-        self.accounts.get(account_id).map(|a| a.into())
+        self.accounts
+            .get(account_id)
+            .map(|a| a.into_account(account_id))
     }
 
     pub(crate) fn internal_get_account(&self, account_id: &AccountId) -> Account {
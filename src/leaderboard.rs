@@ -0,0 +1,353 @@
+use crate::*;
+
+/// Leaderboard entry, buckets group posts by the day they were created.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RankedPost {
+    pub post_id: PostId,
+    pub community_id: Option<CommunityId>,
+    pub score: i64,
+    pub time: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct RankedUser {
+    pub account_id: AccountId,
+    pub reputation: i64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Most voted posts of the last day, `time_decay` favours the most recent ones.
+    pub fn get_hot_posts(
+        &self,
+        from_index: u64,
+        limit: u64,
+        time_decay: Option<bool>,
+    ) -> Vec<PostStats> {
+        self.internal_ranked_posts(ONE_DAY_UNIX_TIME, from_index, limit, time_decay)
+    }
+
+    /// Most voted posts of the last week, `time_decay` favours the most recent ones.
+    pub fn get_trending_posts(
+        &self,
+        from_index: u64,
+        limit: u64,
+        time_decay: Option<bool>,
+    ) -> Vec<PostStats> {
+        self.internal_ranked_posts(ONE_WEEK_UNIX_TIME, from_index, limit, time_decay)
+    }
+}
+
+impl Contract {
+    /// Posts of communities that aren't public are left out, as in the feeds of non-members.
+    fn internal_ranked_posts(
+        &self,
+        window: u64,
+        from_index: u64,
+        limit: u64,
+        time_decay: Option<bool>,
+    ) -> Vec<PostStats> {
+        let now = env::block_timestamp() / 1_000_000_000;
+        let since = now.saturating_sub(window);
+
+        let mut entries: Vec<RankedPost> = (since / ONE_DAY_UNIX_TIME..=now / ONE_DAY_UNIX_TIME)
+            .flat_map(|bucket| self.leaderboard.get(&bucket).unwrap_or_default())
+            .filter(|entry| entry.time / 1_000_000_000 > since)
+            .filter(|entry| {
+                entry
+                    .community_id
+                    .as_ref()
+                    .map(|community_id| self.internal_can_access_community(community_id, None))
+                    .unwrap_or(true)
+            })
+            .collect();
+
+        if time_decay.unwrap_or(false) {
+            entries.sort_by_key(|entry| std::cmp::Reverse(decayed_score(entry, now)));
+        } else {
+            entries.sort_by_key(|entry| std::cmp::Reverse(entry.score));
+        }
+
+        entries
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .map(|entry| PostStats {
+                num_likes: entry.score,
+                post_id: entry.post_id,
+                community_id: entry.community_id,
+            })
+            .collect()
+    }
+
+    /// Applies a vote change to the post score and moves the post in its leaderboard bucket.
+    pub(crate) fn internal_update_post_score(&mut self, post_id: &PostId, delta: i64) {
+        let score = self.vote_counts.get(post_id).unwrap_or(0) + delta;
+        self.vote_counts.insert(post_id, &score);
+
+        if let Some(post) = self.internal_get_post(post_id) {
+            self.internal_add_reputation(&post.account_id, delta * UPVOTE_REPUTATION);
+            self.internal_rank_post(post, score);
+        }
+    }
+
+    pub(crate) fn internal_rank_post(&mut self, post: Post, score: i64) {
+        let bucket = post.time.0 / 1_000_000_000 / ONE_DAY_UNIX_TIME;
+        let mut entries = self.leaderboard.get(&bucket).unwrap_or_default();
+        entries.retain(|entry| entry.post_id != post.id);
+
+        let position = entries
+            .iter()
+            .position(|entry| entry.score < score)
+            .unwrap_or(entries.len());
+        if position < MAX_LEADERBOARD_SIZE {
+            entries.insert(
+                position,
+                RankedPost {
+                    post_id: post.id,
+                    community_id: post.community_id,
+                    score,
+                    time: post.time.0,
+                },
+            );
+            entries.truncate(MAX_LEADERBOARD_SIZE);
+        }
+        self.leaderboard.insert(&bucket, &entries);
+    }
+
+    pub(crate) fn internal_unrank_post(&mut self, post: &Post) {
+        let bucket = post.time.0 / 1_000_000_000 / ONE_DAY_UNIX_TIME;
+        if let Some(mut entries) = self.leaderboard.get(&bucket) {
+            entries.retain(|entry| entry.post_id != post.id);
+            self.leaderboard.insert(&bucket, &entries);
+        }
+    }
+
+    pub(crate) fn internal_add_reputation(&mut self, account_id: &AccountId, delta: i64) {
+        if let Some(mut account) = self.internal_get_account_optional(account_id) {
+            account.reputation += delta;
+            let reputation = account.reputation;
+            self.internal_set_account(account_id, account);
+            self.internal_rank_user(account_id, reputation);
+        }
+    }
+
+    /// Accounts only move when their reputation changes, an account pushed out of a full
+    /// leaderboard comes back on its next gain. The list is only written when the account was
+    /// or gets in it.
+    fn internal_rank_user(&mut self, account_id: &AccountId, reputation: i64) {
+        let mut entries = self.user_leaderboard.get().unwrap_or_default();
+        let num_entries = entries.len();
+        entries.retain(|entry| &entry.account_id != account_id);
+
+        let position = entries
+            .iter()
+            .position(|entry| entry.reputation < reputation)
+            .unwrap_or(entries.len());
+        let ranked = reputation > 0 && position < MAX_TOP_USERS;
+        if !ranked && entries.len() == num_entries {
+            return;
+        }
+        if ranked {
+            entries.insert(
+                position,
+                RankedUser {
+                    account_id: account_id.clone(),
+                    reputation,
+                },
+            );
+            entries.truncate(MAX_TOP_USERS);
+        }
+        self.user_leaderboard.set(&entries);
+    }
+}
+
+/// Score divided by the squared age in hours, offset so fresh posts aren't infinitely boosted.
+fn decayed_score(entry: &RankedPost, now: u64) -> i64 {
+    let age_in_hours = now.saturating_sub(entry.time / 1_000_000_000) / 3_600;
+    entry.score * TIME_DECAY_SCALE / ((age_in_hours + 2) * (age_in_hours + 2)) as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn post_ids(stats: Vec<PostStats>) -> Vec<PostId> {
+        stats.into_iter().map(|stats| stats.post_id).collect()
+    }
+
+    #[test]
+    fn test_hot_and_trending_windows() {
+        let mut contract = setup();
+
+        // The first post is three days old, only trending lists it.
+        for seconds in &[NOW - 3 * ONE_DAY_UNIX_TIME, NOW - 60, NOW - 30] {
+            testing_env!(context(1, *seconds).build());
+            contract.post(
+                "title".to_string(),
+                IPFS_BODY.to_string(),
+                PostType::Website {
+                    url: "https://near.org".to_string(),
+                    site_id: "near".to_string(),
+                },
+                vec!["default".to_string()],
+            );
+        }
+        let old_post = format!("{}_bob", NOW - 3 * ONE_DAY_UNIX_TIME);
+        let recent_post = format!("{}_bob", NOW - 60);
+        let newest_post = format!("{}_bob", NOW - 30);
+
+        testing_env!(context(2, NOW).build());
+        contract.upvote(old_post.clone());
+        contract.upvote(recent_post.clone());
+        contract.upvote(newest_post.clone());
+        testing_env!(context(3, NOW).build());
+        contract.upvote(old_post.clone());
+        contract.upvote(recent_post.clone());
+
+        assert_eq!(contract.get_votes(recent_post.clone()).score, 2);
+        assert_eq!(
+            post_ids(contract.get_hot_posts(0, 10, None)),
+            vec![recent_post.clone(), newest_post.clone()]
+        );
+        assert_eq!(
+            post_ids(contract.get_trending_posts(0, 1, None)),
+            vec![old_post.clone()]
+        );
+
+        contract.unvote(recent_post.clone());
+        contract.unvote(old_post.clone());
+        testing_env!(context(2, NOW).build());
+        contract.unvote(recent_post.clone());
+        assert_eq!(
+            post_ids(contract.get_hot_posts(0, 10, Some(true))),
+            vec![newest_post, recent_post]
+        );
+        assert_eq!(contract.get_trending_posts(2, 10, None).len(), 1);
+    }
+
+    #[test]
+    fn test_private_community_posts_are_not_ranked() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 0, "Rust", CommunityVisibility::Public);
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id.clone());
+        let post = new_community_post(&mut contract, 0, NOW, &community_id);
+        testing_env!(context(2, NOW).build());
+        contract.upvote(post.id.clone());
+        assert_eq!(post_ids(contract.get_hot_posts(0, 10, None)), vec![post.id]);
+
+        testing_env!(context(0, NOW).build());
+        contract.set_community_visibility(community_id, CommunityVisibility::RequestToJoin);
+        assert!(contract.get_hot_posts(0, 10, None).is_empty());
+        assert!(contract.get_trending_posts(0, 10, None).is_empty());
+    }
+
+    #[test]
+    fn test_top_users_by_reputation() {
+        let mut contract = setup();
+
+        testing_env!(context(1, NOW).build());
+        let post = contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["default".to_string()],
+        );
+        testing_env!(context(2, NOW).build());
+        contract.follow(accounts(1));
+        contract.upvote(post.id.clone());
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(3, NOW).build());
+        contract.follow(accounts(1));
+        contract.follow(accounts(2));
+
+        let top = |contract: &Contract, limit| -> Vec<(AccountId, i64)> {
+            contract
+                .top_users(limit)
+                .into_iter()
+                .map(|(account_id, stats)| (account_id, stats.reputation))
+                .collect()
+        };
+        assert_eq!(
+            top(&contract, 10),
+            vec![("bob".to_string(), 6), ("charlie".to_string(), 2)]
+        );
+        assert_eq!(top(&contract, 1).len(), 1);
+
+        contract.unfollow(accounts(1).into());
+        testing_env!(context(2, NOW).build());
+        contract.unvote(post.id.clone());
+        testing_env!(context(0, NOW).build());
+        contract.follow(accounts(2));
+        assert_eq!(
+            top(&contract, 10),
+            vec![("charlie".to_string(), 4), ("bob".to_string(), 3)]
+        );
+    }
+
+    #[test]
+    fn test_comment_reputation_once_per_commenter() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        let reputation =
+            |contract: &Contract| contract.get_account(accounts(1)).unwrap().reputation;
+
+        testing_env!(context(2, NOW).build());
+        let first = contract.comment(post.id.clone(), "body".to_string(), None);
+        testing_env!(context(2, NOW).block_index(1).build());
+        let second = contract.comment(post.id.clone(), "body".to_string(), None);
+        assert_eq!(reputation(&contract), COMMENT_REPUTATION);
+
+        contract.delete_comment(post.id.clone(), first.id);
+        assert_eq!(reputation(&contract), COMMENT_REPUTATION);
+        contract.delete_comment(post.id.clone(), second.id);
+        assert_eq!(reputation(&contract), 0);
+        assert!(contract.top_users(10).is_empty());
+    }
+
+    #[test]
+    fn test_mint_reputation_storage_is_charged() {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        let chest = contract.place_chest(
+            "bob".to_string(),
+            "code".to_string(),
+            "hello".to_string(),
+            None,
+            Location {
+                label: "hanoi".to_string(),
+                lat: 21.0,
+                lng: 105.8,
+            },
+            None,
+        );
+        let used_bytes = contract
+            .storage_accounts
+            .get(&"bob".to_string())
+            .unwrap()
+            .used_bytes;
+
+        testing_env!(
+            context(0, NOW).build(),
+            near_sdk::VMConfig::default(),
+            near_sdk::RuntimeFeesConfig::default(),
+            Default::default(),
+            vec![near_sdk::PromiseResult::Successful(vec![])]
+        );
+        assert!(contract.on_minted_chest(chest.id, Some("charlie".to_string())));
+        assert_eq!(
+            contract.top_users(10)[0].1.reputation,
+            CHEST_MINT_REPUTATION
+        );
+        assert!(
+            contract
+                .storage_accounts
+                .get(&"bob".to_string())
+                .unwrap()
+                .used_bytes
+                > used_bytes
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use std::str;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::collections::{LazyOption, LookupMap, LookupSet, UnorderedMap, UnorderedSet, Vector};
 use near_sdk::json_types::{ValidAccountId, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
@@ -16,9 +16,16 @@ pub use crate::admin::*;
 pub use crate::chest::*;
 pub use crate::comment::*;
 pub use crate::community::*;
+pub use crate::event::*;
 pub use crate::ext_nft::*;
-pub use crate::internal_account::*;
+pub use crate::feed::*;
+pub use crate::group_message::*;
+pub use crate::hashtag::*;
+pub use crate::leaderboard::*;
 pub use crate::like::*;
+pub use crate::migration::*;
+pub use crate::notification::*;
+pub use crate::poll::*;
 pub use crate::post::*;
 pub use crate::private_message::*;
 pub use crate::storage::*;
@@ -32,18 +39,42 @@ type PostId = String;
 type PlaceId = String;
 type ChestId = String;
 
+// The allowed lints are carried over from the original modules.
+#[allow(clippy::redundant_locals)]
 mod account;
+mod account_block;
 mod admin;
+#[allow(
+    clippy::too_many_arguments,
+    clippy::len_zero,
+    unused_must_use,
+    unused_variables
+)]
 mod chest;
 mod comment;
+mod comment_vote;
+#[allow(clippy::unwrap_or_default)]
 mod community;
+mod community_access;
+mod community_moderation;
 mod constant;
+mod event;
+#[allow(clippy::needless_return)]
 mod ext_nft;
+mod feed;
+mod group_message;
+mod hashtag;
 mod internal_account;
+mod leaderboard;
 mod like;
+mod migration;
+mod notification;
+mod poll;
 mod post;
 mod private_message;
 mod storage;
+#[cfg(test)]
+mod test_utils;
 mod topic;
 mod utils;
 
@@ -52,6 +83,8 @@ setup_alloc!();
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize)]
 pub struct Contract {
+    pub owner_id: AccountId,
+    pub pending_owner_id: Option<AccountId>,
     pub ft_contract: AccountId,
 
     pub storage_accounts: LookupMap<AccountId, StorageAccount>,
@@ -61,28 +94,83 @@ pub struct Contract {
     pub posts: UnorderedMap<PostId, VPost>,
     pub user_posts: LookupMap<AccountId, UnorderedSet<PostId>>,
     pub deleted_posts: UnorderedSet<PostId>,
-
+    pub post_revisions: LookupMap<PostId, Vector<PostRevision>>,
+    /// Append-only, deleted posts stay in the indexes as tombstones.
+    pub post_index: Vector<PostIndexEntry>,
+    pub account_post_index: LookupMap<AccountId, Vector<PostIndexEntry>>,
+    pub community_post_index: LookupMap<CommunityId, Vector<PostIndexEntry>>,
+    /// Lowercased hashtag -> posts whose title used it, in the order they were tagged.
+    pub hashtag_post_index: LookupMap<String, Vector<PostIndexEntry>>,
+    /// Pairs already in `hashtag_post_index`, so posts are listed once per hashtag.
+    pub hashtag_posts: LookupSet<(String, PostId)>,
+    /// Account -> posts whose title mentioned it, in the order they were mentioned.
+    pub mention_index: LookupMap<AccountId, Vector<PostIndexEntry>>,
+    /// Pairs already in `mention_index`, so posts are listed once per account.
+    pub mention_posts: LookupSet<(AccountId, PostId)>,
+    pub notifications: LookupMap<AccountId, Vector<Notification>>,
+    /// (account, actor, type, post) of the notifications that are only sent once.
+    pub sent_notifications: LookupSet<(AccountId, AccountId, NotificationType, Option<PostId>)>,
+    pub user_leaderboard: LazyOption<Vec<RankedUser>>,
+
+    /// Latest message of the V1 conversations, moved to `conversation_messages` by their next
+    /// message.
     pub messages: LookupMap<MessageId, PrivateMessage>,
-    pub likes: UnorderedMap<PostId, UnorderedMap<AccountId, u8>>, //get for Hot page
+    /// The last message of the history is the latest one of the conversation.
+    pub conversation_messages: LookupMap<MessageId, Vector<PrivateMessage>>,
+    pub read_receipts: LookupMap<MessageId, UnorderedMap<AccountId, u64>>,
+    pub group_conversations: LookupMap<MessageId, GroupConversation>,
+    pub group_members: LookupMap<MessageId, UnorderedSet<AccountId>>,
+    pub group_messages: LookupMap<MessageId, Vector<GroupMessage>>,
+    pub likes: UnorderedMap<PostId, UnorderedMap<AccountId, u8>>,
+    pub vote_counts: LookupMap<PostId, i64>,
+    /// Reaction code -> number of accounts that reacted with it.
+    pub reaction_counts: LookupMap<PostId, Vec<u64>>,
+    /// Day bucket -> most voted posts created that day, for hot and trending pages.
+    pub leaderboard: LookupMap<u64, Vec<RankedPost>>,
     pub comments: LookupMap<PostId, Vector<Comment>>, //Should use hashmap to store comment
-    pub check_repost: LookupMap<PostId, UnorderedSet<AccountId>>,
+    pub comment_replies: LookupMap<(PostId, CommentId), Vector<CommentId>>,
+    pub comment_votes: LookupMap<(PostId, CommentId), UnorderedSet<AccountId>>,
+    /// Highest scored live comments of each post with their score, newest first among equal
+    /// scores. At most `MAX_TOP_COMMENTS` per post.
+    pub top_comments: LookupMap<PostId, Vec<(CommentId, i64)>>,
+    /// Live comments of each account on a post, the post owner gains reputation once per
+    /// commenter.
+    pub commenters: LookupMap<(PostId, AccountId), u64>,
+    /// Voter -> index of the option they picked.
+    pub poll_votes: LookupMap<PostId, UnorderedMap<AccountId, u32>>,
+    /// Number of votes per option, in the order of the poll options.
+    pub poll_tallies: LookupMap<PostId, Vec<u64>>,
+    pub check_repost: LookupMap<PostId, UnorderedMap<AccountId, PostId>>,
 
     // Topic
     pub topics: UnorderedMap<TopicId, Topic>,
+    /// Live posts of each topic, `topic_post_index` lists them by time.
     pub topics_posts: LookupMap<TopicId, UnorderedSet<PostId>>,
+    pub topic_post_index: LookupMap<TopicId, Vector<PostIndexEntry>>,
+    pub topic_followers: LookupMap<TopicId, UnorderedSet<AccountId>>,
+    pub topic_children: LookupMap<TopicId, UnorderedSet<TopicId>>,
 
     // Community
     pub communities: UnorderedMap<CommunityId, Community>,
-    pub communities_posts: UnorderedMap<CommunityId, UnorderedMap<PostId, VPost>>,
+    /// Live posts of each community, the posts themselves are kept in `posts`.
+    pub community_posts: LookupMap<CommunityId, UnorderedSet<PostId>>,
 
     pub members_in_communites: UnorderedMap<CommunityId, UnorderedSet<AccountId>>,
+    pub community_moderators: LookupMap<CommunityId, UnorderedSet<AccountId>>,
+    pub banned_in_communities: LookupMap<CommunityId, UnorderedSet<AccountId>>,
+    pub community_join_requests: LookupMap<CommunityId, UnorderedSet<AccountId>>,
+    pub community_invites: LookupMap<CommunityId, UnorderedSet<AccountId>>,
     pub storage_account_in_bytes: StorageUsage,
     pub admins: LookupSet<AccountId>,
+    pub roles: LookupMap<Role, UnorderedSet<AccountId>>,
 
     // Chest Item
     pub place_ids: UnorderedSet<PlaceId>,
     pub chests_per_place: LookupMap<PlaceId, UnorderedSet<ChestId>>,
     pub chests: LookupMap<ChestId, Chest>,
+
+    /// Set by `migrate` until `migrate_step` ran every rebuild.
+    pub migration: Option<MigrationV1>,
 }
 
 impl Default for Contract {
@@ -94,9 +182,11 @@ impl Default for Contract {
 #[near_bindgen]
 impl Contract {
     #[init]
-    pub fn new(ft_contract: AccountId) -> Self {
+    pub fn new(owner_id: ValidAccountId, ft_contract: AccountId) -> Self {
         assert!(!env::state_exists(), "Already initialized");
         let mut this = Self {
+            owner_id: owner_id.into(),
+            pending_owner_id: None,
             ft_contract,
 
             storage_accounts: LookupMap::new(StorageKey::StorageAccount),
@@ -105,26 +195,61 @@ impl Contract {
             posts: UnorderedMap::new(StorageKey::Posts),
             user_posts: LookupMap::new(StorageKey::UserPosts),
             deleted_posts: UnorderedSet::new(StorageKey::DeletedPosts),
+            post_revisions: LookupMap::new(StorageKey::PostRevisions),
+            post_index: Vector::new(StorageKey::PostIndex),
+            account_post_index: LookupMap::new(StorageKey::AccountPostIndex),
+            community_post_index: LookupMap::new(StorageKey::CommunityPostIndex),
+            hashtag_post_index: LookupMap::new(StorageKey::HashtagPostIndex),
+            hashtag_posts: LookupSet::new(StorageKey::HashtagPosts),
+            mention_index: LookupMap::new(StorageKey::MentionIndex),
+            mention_posts: LookupSet::new(StorageKey::MentionPosts),
+            notifications: LookupMap::new(StorageKey::Notifications),
+            sent_notifications: LookupSet::new(StorageKey::SentNotifications),
+            user_leaderboard: LazyOption::new(StorageKey::UserLeaderboard, None),
 
             messages: LookupMap::new(StorageKey::Messages),
+            conversation_messages: LookupMap::new(StorageKey::ConversationMessages),
+            read_receipts: LookupMap::new(StorageKey::ReadReceipts),
+            group_conversations: LookupMap::new(StorageKey::GroupConversations),
+            group_members: LookupMap::new(StorageKey::GroupMembers),
+            group_messages: LookupMap::new(StorageKey::GroupMessages),
             likes: UnorderedMap::new(StorageKey::Likes),
+            vote_counts: LookupMap::new(StorageKey::VoteCounts),
+            reaction_counts: LookupMap::new(StorageKey::ReactionCounts),
+            leaderboard: LookupMap::new(StorageKey::Leaderboard),
             comments: LookupMap::new(StorageKey::Commnets),
+            comment_replies: LookupMap::new(StorageKey::CommentReplies),
+            comment_votes: LookupMap::new(StorageKey::CommentVotes),
+            top_comments: LookupMap::new(StorageKey::TopComments),
+            commenters: LookupMap::new(StorageKey::Commenters),
+            poll_votes: LookupMap::new(StorageKey::PollVotes),
+            poll_tallies: LookupMap::new(StorageKey::PollTallies),
             check_repost: LookupMap::new(StorageKey::CheckRePost),
             
 
             topics: UnorderedMap::new(StorageKey::Topics),
             topics_posts: LookupMap::new(StorageKey::TopicsPosts),
+            topic_post_index: LookupMap::new(StorageKey::TopicPostIndex),
+            topic_followers: LookupMap::new(StorageKey::TopicFollowers),
+            topic_children: LookupMap::new(StorageKey::TopicChildren),
 
             communities: UnorderedMap::new(StorageKey::Communities),
-            communities_posts: UnorderedMap::new(StorageKey::CommunitiesPosts),
+            community_posts: LookupMap::new(StorageKey::CommunityPosts),
 
             members_in_communites: UnorderedMap::new(StorageKey::MemberInCommunites),
+            community_moderators: LookupMap::new(StorageKey::CommunityModerators),
+            banned_in_communities: LookupMap::new(StorageKey::BannedInCommunities),
+            community_join_requests: LookupMap::new(StorageKey::CommunityJoinRequests),
+            community_invites: LookupMap::new(StorageKey::CommunityInvites),
             storage_account_in_bytes: 0,
             admins: LookupSet::new(StorageKey::Admins),
+            roles: LookupMap::new(StorageKey::Roles),
 
             place_ids: UnorderedSet::new(StorageKey::PlaceIds),
             chests_per_place: LookupMap::new(StorageKey::ChestsPerPlace),
             chests: LookupMap::new(StorageKey::Chests),
+
+            migration: None,
         };
 
         let account_id = env::predecessor_account_id();
@@ -135,12 +260,16 @@ impl Contract {
             id: topic_id.clone(),
             name: "Default".to_string(),
             admin: ValidAccountId::try_from(account_id).unwrap(),
+            thumbnail: "".to_string(),
             created_time: env::block_timestamp().into(),
             description: "Default topics for all post".to_string(),
+            archived: false,
+            parent_id: None,
         };
 
         this.topics.insert(&topic_id, &topic);
 
+        Self::internal_set_state_version();
         this.measure_storage_account_in_bytes();
         this
     }
@@ -1,77 +1,136 @@
 use super::*;
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+/// Reaction of an account to a post, stored as its `u8` code in `likes`.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
 #[serde(crate = "near_sdk::serde")]
 #[serde(tag = "type")]
 pub enum VoteStatus {
     UpVote,
-    // DownVote,
+    DownVote,
+    Love,
+    Haha,
+    Wow,
+    Sad,
+    Angry,
     Default,
 }
 
+impl VoteStatus {
+    /// Codes 0 and 1 are the values V1 stored for downvotes and upvotes.
+    fn code(self) -> u8 {
+        match self {
+            VoteStatus::DownVote => 0,
+            VoteStatus::UpVote => 1,
+            VoteStatus::Love => 2,
+            VoteStatus::Haha => 3,
+            VoteStatus::Wow => 4,
+            VoteStatus::Sad => 5,
+            VoteStatus::Angry => 6,
+            VoteStatus::Default => env::panic(b"Default is not a reaction"),
+        }
+    }
+
+    fn from_code(code: u8) -> Self {
+        match code {
+            0 => VoteStatus::DownVote,
+            1 => VoteStatus::UpVote,
+            2 => VoteStatus::Love,
+            3 => VoteStatus::Haha,
+            4 => VoteStatus::Wow,
+            5 => VoteStatus::Sad,
+            6 => VoteStatus::Angry,
+            _ => VoteStatus::Default,
+        }
+    }
+
+    /// Only up and down votes move the post score, the other reactions are counted apart.
+    fn score(self) -> i64 {
+        match self {
+            VoteStatus::UpVote => 1,
+            VoteStatus::DownVote => -1,
+            _ => 0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteStats {
+    pub score: i64,
+    pub up_vote: u64,
+    pub down_vote: u64,
+    pub love: u64,
+    pub haha: u64,
+    pub wow: u64,
+    pub sad: u64,
+    pub angry: u64,
+}
+
 #[near_bindgen]
 impl Contract {
     pub fn upvote(&mut self, post_id: PostId) -> bool {
+        self.react(post_id, VoteStatus::UpVote)
+    }
+
+    pub fn downvote(&mut self, post_id: PostId) -> bool {
+        self.react(post_id, VoteStatus::DownVote)
+    }
+
+    /// Replaces the previous reaction of the caller, if any. Authors can't react to their own
+    /// posts, votes move their reputation.
+    pub fn react(&mut self, post_id: PostId, reaction: VoteStatus) -> bool {
+        assert!(
+            reaction != VoteStatus::Default,
+            "Use unvote to remove your reaction"
+        );
         let account_id = env::predecessor_account_id();
+        let post = self.internal_get_post(&post_id).expect("Post not found");
+        assert!(
+            post.account_id != account_id,
+            "Can't react to your own post"
+        );
+        self.assert_can_interact_with_post(&post, &account_id);
 
         let storage_update = self.new_storage_update(account_id.clone());
 
-        if let Some(mut likes_map) = self.likes.get(&post_id) {
-            if let Some(vote_value) = likes_map.get(&account_id) {
-                assert!(vote_value == 0, "You already upvote!");
-                likes_map.insert(&account_id, &1);
-            } else {
-                likes_map.insert(&account_id, &1);
-            }
-            self.likes.insert(&post_id, &likes_map);
-
-            true
-        } else {
+        let mut likes_map = self.likes.get(&post_id).unwrap_or_else(|| {
             let mut like_key = vec![b'l'];
 
             let hash = env::sha256(post_id.as_bytes());
             like_key.extend_from_slice(&hash);
 
-            let mut values = UnorderedMap::new(like_key);
-
-            values.insert(&account_id, &1);
-            self.likes.insert(&post_id, &values);
-            self.finalize_storage_update(storage_update);
-
-            true
+            UnorderedMap::new(like_key)
+        });
+        let previous = likes_map
+            .insert(&account_id, &reaction.code())
+            .map(VoteStatus::from_code);
+        assert!(
+            previous != Some(reaction),
+            "You already reacted with this reaction!"
+        );
+        self.likes.insert(&post_id, &likes_map);
+        self.internal_count_reaction(&post_id, previous, Some(reaction));
+        if reaction == VoteStatus::UpVote {
+            self.internal_notify(
+                &post.account_id,
+                &account_id,
+                NotificationKind::Upvote {
+                    post_id: post_id.clone(),
+                },
+            );
         }
-    }
+        self.finalize_storage_update(storage_update);
 
-    // pub fn downvote(&mut self, post_id: PostId) -> bool {
-    //     let account_id = env::predecessor_account_id();
-    //
-    //     let storage_update = self.new_storage_update(account_id.clone());
-    //
-    //     if let Some(mut likes_map) = self.likes.get(&post_id) {
-    //         if let Some(vote_value) = likes_map.get(&account_id) {
-    //             assert!(vote_value == 1, "You already downvote!");
-    //             likes_map.insert(&account_id, &0);
-    //         } else {
-    //             likes_map.insert(&account_id, &0);
-    //         }
-    //         self.likes.insert(&post_id, &likes_map);
-    //
-    //         true
-    //     } else {
-    //         let mut like_key = vec![b'l'];
-    //
-    //         let hash = env::sha256(post_id.as_bytes());
-    //         like_key.extend_from_slice(&hash);
-    //
-    //         let mut values = UnorderedMap::new(like_key);
-    //
-    //         values.insert(&account_id, &0);
-    //         self.likes.insert(&post_id, &values);
-    //         self.finalize_storage_update(storage_update);
-    //
-    //         true
-    //     }
-    // }
+        Event::Vote(VoteData {
+            post_id: &post_id,
+            account_id: &account_id,
+            reaction,
+        })
+        .emit();
+        true
+    }
 
     pub fn unvote(&mut self, post_id: PostId) {
         let account_id = env::predecessor_account_id();
@@ -79,51 +138,161 @@ impl Contract {
         let storage_update = self.new_storage_update(account_id.clone());
 
         if let Some(mut likes_map) = self.likes.get(&post_id) {
-            likes_map.remove(&account_id);
-            self.likes.insert(&post_id, &likes_map);
-            self.finalize_storage_update(storage_update);
+            if let Some(code) = likes_map.remove(&account_id) {
+                let reaction = VoteStatus::from_code(code);
+                self.likes.insert(&post_id, &likes_map);
+                self.internal_count_reaction(&post_id, Some(reaction), None);
+                self.finalize_storage_update(storage_update);
+
+                Event::Unvote(VoteData {
+                    post_id: &post_id,
+                    account_id: &account_id,
+                    reaction,
+                })
+                .emit();
+            }
         }
     }
 
-    pub fn get_votes(&self, post_id: PostId) -> i64 {
-        let upvotes = self
-            .likes
-            .get(&post_id)
-            .unwrap_or_else(|| UnorderedMap::new(b"x"))
-            .iter()
-            .filter(|(_k, v)| v == &1)
-            .count() as i64;
-        2 * upvotes
-            - self
-                .likes
-                .get(&post_id)
-                .unwrap_or_else(|| UnorderedMap::new(b"x"))
-                .len() as i64
+    pub fn get_votes(&self, post_id: PostId) -> VoteStats {
+        let counts = self.reaction_counts.get(&post_id).unwrap_or_default();
+        let count =
+            |reaction: VoteStatus| counts.get(reaction.code() as usize).copied().unwrap_or(0);
+        VoteStats {
+            score: self.vote_counts.get(&post_id).unwrap_or(0),
+            up_vote: count(VoteStatus::UpVote),
+            down_vote: count(VoteStatus::DownVote),
+            love: count(VoteStatus::Love),
+            haha: count(VoteStatus::Haha),
+            wow: count(VoteStatus::Wow),
+            sad: count(VoteStatus::Sad),
+            angry: count(VoteStatus::Angry),
+        }
     }
 
-    // pub fn get_downvotes(&self, post_id: PostId) -> u64 {
-    //     self.likes
-    //         .get(&post_id)
-    //         .unwrap_or(UnorderedMap::new(b"x"))
-    //         .iter()
-    //         .filter(|(_k, v)| v == &0)
-    //         .collect::<Vec<(AccountId, u8)>>()
-    //         .len() as u64
-    // }
-
     pub fn vote_status(&self, post_id: PostId, account_id: AccountId) -> VoteStatus {
-        if let Some(values) = self.likes.get(&post_id) {
-            let vote_status = values.get(&account_id);
-            if vote_status == Some(1) {
-                VoteStatus::UpVote
-            } else {
-                VoteStatus::Default
+        self.likes
+            .get(&post_id)
+            .and_then(|values| values.get(&account_id))
+            .map(VoteStatus::from_code)
+            .unwrap_or(VoteStatus::Default)
+    }
+}
+
+impl Contract {
+    /// Moves one reaction from `previous` to `current` in the counters and the post score.
+    pub(crate) fn internal_count_reaction(
+        &mut self,
+        post_id: &PostId,
+        previous: Option<VoteStatus>,
+        current: Option<VoteStatus>,
+    ) {
+        let mut counts = self.reaction_counts.get(post_id).unwrap_or_default();
+        if let Some(previous) = previous {
+            if let Some(count) = counts.get_mut(previous.code() as usize) {
+                *count = count.saturating_sub(1);
             }
-        } else {
-            VoteStatus::Default
         }
+        if let Some(current) = current {
+            let index = current.code() as usize;
+            if counts.len() <= index {
+                counts.resize(index + 1, 0);
+            }
+            counts[index] += 1;
+        }
+        self.reaction_counts.insert(post_id, &counts);
+
+        let delta = current.map_or(0, VoteStatus::score) - previous.map_or(0, VoteStatus::score);
+        if delta != 0 {
+            self.internal_update_post_score(post_id, delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    #[test]
+    fn test_votes_and_reactions() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(0, NOW).build());
+        contract.upvote(post.id.clone());
+        testing_env!(context(2, NOW).build());
+        contract.downvote(post.id.clone());
+        testing_env!(context(3, NOW).build());
+        contract.react(post.id.clone(), VoteStatus::Love);
+
+        let stats = contract.get_votes(post.id.clone());
+        assert_eq!(stats.score, 0);
+        assert_eq!((stats.up_vote, stats.down_vote, stats.love), (1, 1, 1));
+        assert_eq!(
+            contract.vote_status(post.id.clone(), accounts(2).into()),
+            VoteStatus::DownVote
+        );
+        assert_eq!(
+            contract.vote_status(post.id.clone(), accounts(1).into()),
+            VoteStatus::Default
+        );
+    }
+
+    #[test]
+    fn test_switching_reactions_moves_the_counts() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(2, NOW).build());
+        contract.downvote(post.id.clone());
+        assert_eq!(contract.get_votes(post.id.clone()).score, -1);
+
+        contract.upvote(post.id.clone());
+        let stats = contract.get_votes(post.id.clone());
+        assert_eq!((stats.score, stats.up_vote, stats.down_vote), (1, 1, 0));
+
+        contract.react(post.id.clone(), VoteStatus::Haha);
+        let stats = contract.get_votes(post.id.clone());
+        assert_eq!((stats.score, stats.up_vote, stats.haha), (0, 0, 1));
+        assert_eq!(
+            contract.reaction_counts.get(&post.id).unwrap(),
+            vec![0, 0, 0, 1]
+        );
+
+        contract.unvote(post.id.clone());
+        let stats = contract.get_votes(post.id.clone());
+        assert_eq!((stats.score, stats.haha), (0, 0));
+        assert_eq!(
+            contract.vote_status(post.id, accounts(2).into()),
+            VoteStatus::Default
+        );
     }
 
+    #[test]
+    #[should_panic(expected = "You already reacted with this reaction!")]
+    fn test_same_reaction_twice() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.react(post.id.clone(), VoteStatus::Wow);
+        contract.react(post.id, VoteStatus::Wow);
+    }
 
-    
+    #[test]
+    #[should_panic(expected = "Can't react to your own post")]
+    fn test_upvote_own_post() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.upvote(post.id);
+    }
+
+    #[test]
+    #[should_panic(expected = "Use unvote to remove your reaction")]
+    fn test_default_is_not_a_reaction() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.react(post.id, VoteStatus::Default);
+    }
 }
@@ -0,0 +1,1077 @@
+use crate::*;
+
+/// Version of the `Contract` layout written by this code.
+pub const STATE_VERSION: u32 = 2;
+
+/// Raw storage key holding the state version, it is kept outside of `Contract`
+/// so `migrate` can read it before knowing which layout to deserialize.
+pub const STATE_VERSION_KEY: &[u8] = b"STATE_VERSION";
+
+/// Layout deployed before the state version was stored.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct ContractV1 {
+    pub ft_contract: AccountId,
+
+    pub storage_accounts: LookupMap<AccountId, StorageAccount>,
+    pub accounts: UnorderedMap<AccountId, VAccount>,
+
+    pub posts: UnorderedMap<PostId, VPost>,
+    pub user_posts: LookupMap<AccountId, UnorderedSet<PostId>>,
+    pub deleted_posts: UnorderedSet<PostId>,
+
+    pub messages: LookupMap<MessageId, PrivateMessage>,
+    pub likes: UnorderedMap<PostId, UnorderedMap<AccountId, u8>>,
+    pub comments: LookupMap<PostId, Vector<CommentV1>>,
+    pub check_repost: LookupMap<PostId, UnorderedSet<AccountId>>,
+
+    pub topics: UnorderedMap<TopicId, TopicV1>,
+    pub topics_posts: LookupMap<TopicId, UnorderedSet<PostId>>,
+
+    pub communities: UnorderedMap<CommunityId, CommunityV1>,
+    pub communities_posts: UnorderedMap<CommunityId, UnorderedMap<PostId, VPost>>,
+
+    pub members_in_communites: UnorderedMap<CommunityId, UnorderedSet<AccountId>>,
+    pub storage_account_in_bytes: StorageUsage,
+    pub admins: LookupSet<AccountId>,
+
+    pub place_ids: UnorderedSet<PlaceId>,
+    pub chests_per_place: LookupMap<PlaceId, UnorderedSet<ChestId>>,
+    pub chests: LookupMap<ChestId, Chest>,
+}
+
+/// Post layout of `ContractV1`, stored as the first `VPost` variant.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostV1 {
+    pub id: PostId,
+    pub account_id: AccountId,
+    pub topic: TopicV1,
+    pub title: String,
+    pub body: String,
+    pub post_type: PostType,
+    pub time: U64,
+    pub num_quote: u32,
+}
+
+impl From<PostV1> for Post {
+    fn from(post: PostV1) -> Self {
+        Self {
+            id: post.id,
+            account_id: post.account_id,
+            topics: vec![post.topic.id],
+            title: post.title,
+            body: post.body,
+            post_type: post.post_type,
+            time: post.time,
+            num_quote: post.num_quote,
+            repost_of: None,
+            community_id: None,
+            edited_at: None,
+        }
+    }
+}
+
+/// Community layout of `ContractV1`, every community was public.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CommunityV1 {
+    pub id: CommunityId,
+    pub admin: ValidAccountId,
+    pub thumbnail: String,
+    pub avatar: String,
+    pub name: String,
+    pub created_time: U64,
+    pub description: String,
+}
+
+impl From<CommunityV1> for Community {
+    fn from(community: CommunityV1) -> Self {
+        Self {
+            id: community.id,
+            admin: community.admin,
+            thumbnail: community.thumbnail,
+            avatar: community.avatar,
+            name: community.name,
+            created_time: community.created_time,
+            description: community.description,
+            visibility: CommunityVisibility::Public,
+        }
+    }
+}
+
+/// Topic layout of `ContractV1`, topics had no thumbnail and could not be archived.
+#[derive(BorshDeserialize, BorshSerialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TopicV1 {
+    pub id: TopicId,
+    pub admin: ValidAccountId,
+    pub name: String,
+    pub created_time: U64,
+    pub description: String,
+}
+
+impl From<TopicV1> for Topic {
+    fn from(topic: TopicV1) -> Self {
+        Self {
+            id: topic.id,
+            admin: topic.admin,
+            thumbnail: "".to_string(),
+            name: topic.name,
+            created_time: topic.created_time,
+            description: topic.description,
+            archived: false,
+            parent_id: None,
+        }
+    }
+}
+
+/// Comment layout of `ContractV1`, comments had no id and could not be deleted.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct CommentV1 {
+    pub owner: AccountId,
+    pub body: String,
+    pub time: U64,
+}
+
+/// Account layout of `ContractV1`, stored as the first `VAccount` variant.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct AccountV1 {
+    pub following: UnorderedSet<AccountId>,
+    pub followers: UnorderedSet<AccountId>,
+    pub chests: Vec<ChestId>,
+    pub bookmarks: Vec<PostId>,
+    pub related_conversations: UnorderedSet<MessageId>,
+    pub message_pub_key: String,
+    pub avatar: String,
+    pub thumbnail: String,
+    pub display_name: String,
+    pub bio: String,
+    pub joined_communities: UnorderedSet<CommunityId>,
+}
+
+impl AccountV1 {
+    /// Upgraded lazily on read, the new collections need the account ID for their prefix.
+    pub(crate) fn into_account(self, account_id: &AccountId) -> Account {
+        Account {
+            following: self.following,
+            followers: self.followers,
+            chests: self.chests,
+            bookmarks: self.bookmarks,
+            related_conversations: self.related_conversations,
+            message_pub_key: self.message_pub_key,
+            avatar: self.avatar,
+            thumbnail: self.thumbnail,
+            display_name: self.display_name,
+            bio: self.bio,
+            joined_communities: self.joined_communities,
+            followed_topics: UnorderedSet::new(account_prefix(b't', account_id)),
+            blocked: UnorderedSet::new(account_prefix(b'b', account_id)),
+            muted: UnorderedSet::new(account_prefix(b'm', account_id)),
+            reputation: 0,
+            num_notifications: 0,
+            notifications_read: 0,
+            notification_opt_outs: vec![],
+        }
+    }
+}
+
+/// Rebuilds left after `migrate`, `migrate_step` runs them in this order.
+#[derive(
+    BorshDeserialize, BorshSerialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum MigrationStep {
+    /// Rewrites the V1 topics with the new layout.
+    Topics,
+    /// Rewrites the V1 communities with the new layout.
+    Communities,
+    /// Empties the V1 `topics_posts` sets, which were keyed by author.
+    TopicsPosts,
+    /// Indexes the V1 posts by time, topic, hashtag and mention.
+    Posts,
+    /// Copies the V1 community posts to the single post store.
+    CommunityPosts,
+    /// Empties the V1 community post maps.
+    ClearCommunityPosts,
+    /// Rewrites the V1 comments of live and deleted posts with ids.
+    Comments,
+    /// Counts the V1 votes into reaction counts, scores and reputation.
+    Votes,
+    /// Adds the reputation of the V1 followers.
+    Followers,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MigrationProgress {
+    pub step: MigrationStep,
+    /// `from_index` of the next `migrate_step` call.
+    pub next_index: u64,
+}
+
+/// State of a migration from V1 that is still running.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct MigrationV1 {
+    pub step: MigrationStep,
+    pub next_index: u64,
+    /// Comment, vote or community post reached in the entry at `next_index`, entries with
+    /// many of them take several calls.
+    pub entry_index: u64,
+    /// Copied by `MigrationStep::CommunityPosts`, drained by
+    /// `MigrationStep::ClearCommunityPosts`.
+    pub communities_posts: UnorderedMap<CommunityId, UnorderedMap<PostId, VPost>>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Only changes the layout of the contract, the rebuilds are run by `migrate_step`.
+    #[private]
+    #[init(ignore_state)]
+    pub fn migrate() -> Self {
+        let this = match Self::internal_state_version() {
+            1 => Self::migrate_from_v1(env::state_read().expect("Failed to read old state")),
+            STATE_VERSION => env::panic(b"State is already up to date"),
+            version => env::panic(format!("Unknown state version {}", version).as_bytes()),
+        };
+
+        Self::internal_set_state_version();
+        this
+    }
+
+    pub fn get_state_version(&self) -> u32 {
+        Self::internal_state_version()
+    }
+
+    /// `None` once every rebuild ran.
+    pub fn get_migration_progress(&self) -> Option<MigrationProgress> {
+        self.migration.as_ref().map(|migration| MigrationProgress {
+            step: migration.step,
+            next_index: migration.next_index,
+        })
+    }
+
+    /// Owner only. Runs the current rebuild on at most `limit` entries starting at
+    /// `from_index`, which must be the `next_index` of the progress. Returns the progress to
+    /// resume from, `None` once the migration finished. Until then the contract only serves
+    /// views and other calls that store data fail.
+    pub fn migrate_step(&mut self, from_index: u64, limit: u64) -> Option<MigrationProgress> {
+        self.assert_owner();
+        let migration = self.migration.as_ref().expect("No migration is running");
+        assert_eq!(
+            from_index, migration.next_index,
+            "The migration resumes from index {}",
+            migration.next_index
+        );
+
+        let done = match migration.step {
+            MigrationStep::Topics => {
+                upgrade_unordered_map::<_, TopicV1, _>(&self.topics, from_index, limit)
+            }
+            MigrationStep::Communities => {
+                upgrade_unordered_map::<_, CommunityV1, _>(&self.communities, from_index, limit)
+            }
+            MigrationStep::TopicsPosts => self.internal_clear_v1_topics_posts(from_index, limit),
+            MigrationStep::Posts => self.internal_migrate_posts(from_index, limit),
+            MigrationStep::CommunityPosts => {
+                self.internal_migrate_community_posts(from_index, limit)
+            }
+            MigrationStep::ClearCommunityPosts => self.internal_clear_v1_community_posts(limit),
+            MigrationStep::Comments => self.internal_migrate_comments(from_index, limit),
+            MigrationStep::Votes => self.internal_migrate_votes(from_index, limit),
+            MigrationStep::Followers => self.internal_migrate_followers(from_index, limit),
+        };
+
+        let migration = self.migration.as_mut().unwrap();
+        migration.next_index = done.unwrap_or(0);
+        if done.is_none() {
+            migration.step = match migration.step {
+                MigrationStep::Topics => MigrationStep::Communities,
+                MigrationStep::Communities => MigrationStep::TopicsPosts,
+                MigrationStep::TopicsPosts => MigrationStep::Posts,
+                MigrationStep::Posts => MigrationStep::CommunityPosts,
+                MigrationStep::CommunityPosts => MigrationStep::ClearCommunityPosts,
+                MigrationStep::ClearCommunityPosts => MigrationStep::Comments,
+                MigrationStep::Comments => MigrationStep::Votes,
+                MigrationStep::Votes => MigrationStep::Followers,
+                MigrationStep::Followers => {
+                    self.migration = None;
+                    return None;
+                }
+            };
+        }
+        self.get_migration_progress()
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_state_version() -> u32 {
+        match env::storage_read(STATE_VERSION_KEY) {
+            Some(bytes) => u32::try_from_slice(&bytes).expect("Invalid state version"),
+            None => 1,
+        }
+    }
+
+    pub(crate) fn internal_set_state_version() {
+        env::storage_write(STATE_VERSION_KEY, &STATE_VERSION.try_to_vec().unwrap());
+    }
+
+    pub(crate) fn assert_migrated(&self) {
+        assert!(
+            self.migration.is_none(),
+            "The contract state is being migrated"
+        );
+    }
+
+    /// V1 had no owner, the contract account could manage admins so it becomes the owner.
+    /// Existing admins keep their admin rights, roles have to be granted again.
+    fn migrate_from_v1(old_state: ContractV1) -> Self {
+        Self {
+            owner_id: env::current_account_id(),
+            pending_owner_id: None,
+            ft_contract: old_state.ft_contract,
+
+            storage_accounts: old_state.storage_accounts,
+            accounts: old_state.accounts,
+
+            posts: old_state.posts,
+            user_posts: old_state.user_posts,
+            deleted_posts: old_state.deleted_posts,
+            post_revisions: LookupMap::new(StorageKey::PostRevisions),
+            post_index: Vector::new(StorageKey::PostIndex),
+            account_post_index: LookupMap::new(StorageKey::AccountPostIndex),
+            community_post_index: LookupMap::new(StorageKey::CommunityPostIndex),
+            hashtag_post_index: LookupMap::new(StorageKey::HashtagPostIndex),
+            hashtag_posts: LookupSet::new(StorageKey::HashtagPosts),
+            mention_index: LookupMap::new(StorageKey::MentionIndex),
+            mention_posts: LookupSet::new(StorageKey::MentionPosts),
+            notifications: LookupMap::new(StorageKey::Notifications),
+            sent_notifications: LookupSet::new(StorageKey::SentNotifications),
+            user_leaderboard: LazyOption::new(StorageKey::UserLeaderboard, None),
+
+            messages: old_state.messages,
+            conversation_messages: LookupMap::new(StorageKey::ConversationMessages),
+            read_receipts: LookupMap::new(StorageKey::ReadReceipts),
+            group_conversations: LookupMap::new(StorageKey::GroupConversations),
+            group_members: LookupMap::new(StorageKey::GroupMembers),
+            group_messages: LookupMap::new(StorageKey::GroupMessages),
+            likes: old_state.likes,
+            vote_counts: LookupMap::new(StorageKey::VoteCounts),
+            reaction_counts: LookupMap::new(StorageKey::ReactionCounts),
+            leaderboard: LookupMap::new(StorageKey::Leaderboard),
+            // Rewritten in place by `MigrationStep::Comments`, the vectors keep their prefixes.
+            comments: cast_collection(&old_state.comments),
+            comment_replies: LookupMap::new(StorageKey::CommentReplies),
+            comment_votes: LookupMap::new(StorageKey::CommentVotes),
+            top_comments: LookupMap::new(StorageKey::TopComments),
+            commenters: LookupMap::new(StorageKey::Commenters),
+            poll_votes: LookupMap::new(StorageKey::PollVotes),
+            poll_tallies: LookupMap::new(StorageKey::PollTallies),
+            // Reposts were never recorded in V1.
+            check_repost: LookupMap::new(StorageKey::CheckRePost),
+
+            topics: cast_collection(&old_state.topics),
+            topics_posts: old_state.topics_posts,
+            topic_post_index: LookupMap::new(StorageKey::TopicPostIndex),
+            topic_followers: LookupMap::new(StorageKey::TopicFollowers),
+            topic_children: LookupMap::new(StorageKey::TopicChildren),
+
+            communities: cast_collection(&old_state.communities),
+            community_posts: LookupMap::new(StorageKey::CommunityPosts),
+
+            members_in_communites: old_state.members_in_communites,
+            community_moderators: LookupMap::new(StorageKey::CommunityModerators),
+            banned_in_communities: LookupMap::new(StorageKey::BannedInCommunities),
+            community_join_requests: LookupMap::new(StorageKey::CommunityJoinRequests),
+            community_invites: LookupMap::new(StorageKey::CommunityInvites),
+            storage_account_in_bytes: old_state.storage_account_in_bytes,
+            admins: old_state.admins,
+            roles: LookupMap::new(StorageKey::Roles),
+
+            place_ids: old_state.place_ids,
+            chests_per_place: old_state.chests_per_place,
+            chests: old_state.chests,
+
+            migration: Some(MigrationV1 {
+                step: MigrationStep::Topics,
+                next_index: 0,
+                entry_index: 0,
+                communities_posts: old_state.communities_posts,
+            }),
+        }
+    }
+
+    /// V1 sets share the prefixes of the topic sets, a topic named like an author would read
+    /// the posts of that author. `next_index` is the position of the account being emptied.
+    fn internal_clear_v1_topics_posts(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let account_ids = self.accounts.keys_as_vector();
+        let mut index = from_index;
+        let mut removed = 0;
+        while removed < limit && index < account_ids.len() {
+            let account_id = account_ids.get(index).unwrap();
+            let visited = removed;
+            let mut done = true;
+            if let Some(mut topic_posts) = self.topics_posts.get(&account_id) {
+                removed += remove_last_elements(&mut topic_posts, limit - removed);
+                done = topic_posts.is_empty();
+                if done {
+                    self.topics_posts.remove(&account_id);
+                } else {
+                    self.topics_posts.insert(&account_id, &topic_posts);
+                }
+            }
+            if removed == visited {
+                removed += 1;
+            }
+            if done {
+                index += 1;
+            }
+        }
+        next_index(index, account_ids.len())
+    }
+
+    /// V1 kept `topics_posts` by author and had no time index. The post store is in creation
+    /// order apart from the posts swapped into the place of deleted ones, the indexes move
+    /// these back to their time.
+    fn internal_migrate_posts(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let to = std::cmp::min(from_index + limit, self.posts.len());
+        for index in from_index..to {
+            let post: Post = self.posts.values_as_vector().get(index).unwrap().into();
+            self.internal_index_post(&post);
+            self.internal_insert_topic_post(&post);
+            self.internal_index_post_tags(&post, "");
+        }
+        next_index(to, self.posts.len())
+    }
+
+    /// V1 kept community posts in a map per community. `next_index` is the position of the
+    /// community, the posts are copied in the order of its map so the indexes stay cheap to
+    /// keep sorted.
+    fn internal_migrate_community_posts(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let mut migration = self.migration.take().unwrap();
+        let num_communities = migration.communities_posts.len();
+        let mut index = from_index;
+        let mut entry_index = migration.entry_index;
+        let mut copied = 0;
+        while copied < limit && index < num_communities {
+            let community_ids = migration.communities_posts.keys_as_vector();
+            let community_id = community_ids.get(index).unwrap();
+            let community_posts = migration.communities_posts.get(&community_id).unwrap();
+            let posts = community_posts.values_as_vector();
+            while copied < limit && entry_index < posts.len() {
+                let post = posts.get(entry_index).unwrap();
+                self.internal_insert_post(Post {
+                    community_id: Some(community_id.clone()),
+                    ..post.into()
+                });
+                entry_index += 1;
+                copied += 1;
+            }
+            if entry_index < posts.len() {
+                break;
+            }
+            if posts.is_empty() {
+                copied += 1;
+            }
+            entry_index = 0;
+            index += 1;
+        }
+
+        migration.entry_index = entry_index;
+        self.migration = Some(migration);
+        next_index(index, num_communities)
+    }
+
+    /// Drains the copied V1 community posts from the end of each map. `next_index` counts the
+    /// removed posts.
+    fn internal_clear_v1_community_posts(&mut self, limit: u64) -> Option<u64> {
+        let mut migration = self.migration.take().unwrap();
+        let mut removed = 0;
+        while removed < limit && !migration.communities_posts.is_empty() {
+            let community_ids = migration.communities_posts.keys_as_vector();
+            let community_id = community_ids.get(community_ids.len() - 1).unwrap();
+            let mut community_posts = migration.communities_posts.get(&community_id).unwrap();
+            while removed < limit && !community_posts.is_empty() {
+                let post_ids = community_posts.keys_as_vector();
+                let post_id = post_ids.get(post_ids.len() - 1).unwrap();
+                community_posts.remove(&post_id);
+                removed += 1;
+            }
+            if community_posts.is_empty() {
+                migration.communities_posts.remove(&community_id);
+            } else {
+                migration
+                    .communities_posts
+                    .insert(&community_id, &community_posts);
+            }
+        }
+
+        let done = migration.communities_posts.is_empty();
+        let next_index = migration.next_index + removed;
+        self.migration = Some(migration);
+        if done {
+            None
+        } else {
+            Some(next_index)
+        }
+    }
+
+    /// Walks the live posts, then the deleted ones, `limit` counts the comments. Comments of
+    /// live posts add to the reputation of their owner.
+    fn internal_migrate_comments(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let num_posts = self.posts.len() + self.deleted_posts.len();
+        let mut index = from_index;
+        let mut processed = 0;
+        while processed < limit && index < num_posts {
+            let post_id = if index < self.posts.len() {
+                self.posts.keys_as_vector().get(index)
+            } else {
+                self.deleted_posts.as_vector().get(index - self.posts.len())
+            }
+            .unwrap();
+            let post_owner = self.internal_get_post(&post_id).map(|post| post.account_id);
+            let start = self.migration.as_ref().unwrap().entry_index;
+            let mut end = start;
+            if let Some(mut comments) = self.comments.get(&post_id) {
+                end = std::cmp::min(start + limit - processed, comments.len());
+                for index in start..end {
+                    let comment =
+                        CommentV1::try_from_slice(&comments.get_raw(index).unwrap()).unwrap();
+                    if let Some(post_owner) = &post_owner {
+                        self.internal_add_commenter(&post_id, post_owner, &comment.owner);
+                    }
+                    let comment = Comment {
+                        id: index,
+                        parent_id: None,
+                        owner: comment.owner,
+                        body: comment.body,
+                        time: comment.time,
+                        deleted: false,
+                        score: 0,
+                    };
+                    comments.replace_raw(index, &comment.try_to_vec().unwrap());
+                }
+                if end < comments.len() {
+                    self.migration.as_mut().unwrap().entry_index = end;
+                    break;
+                }
+                // V1 comments had no score, the newest ones are the top ones.
+                let top: Vec<(CommentId, i64)> = (0..comments.len())
+                    .rev()
+                    .take(MAX_TOP_COMMENTS)
+                    .map(|comment_id| (comment_id, 0))
+                    .collect();
+                self.top_comments.insert(&post_id, &top);
+            }
+            processed += std::cmp::max(end - start, 1);
+            self.migration.as_mut().unwrap().entry_index = 0;
+            index += 1;
+        }
+        next_index(index, num_posts)
+    }
+
+    /// Votes were counted on read in V1, only up and down votes existed. `limit` counts the
+    /// votes, a post with more of them is counted over several calls.
+    fn internal_migrate_votes(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let mut index = from_index;
+        let mut processed = 0;
+        while processed < limit && index < self.likes.len() {
+            let post_id = self.likes.keys_as_vector().get(index).unwrap();
+            let likes = self.likes.values_as_vector().get(index).unwrap();
+            let codes = likes.values_as_vector();
+            let start = self.migration.as_ref().unwrap().entry_index;
+            let end = std::cmp::min(start + limit - processed, codes.len());
+            let up_votes = (start..end)
+                .filter(|position| codes.get(*position).unwrap() == 1)
+                .count() as u64;
+            let down_votes = end - start - up_votes;
+
+            let mut counts = self.reaction_counts.get(&post_id).unwrap_or_default();
+            counts.resize(2, 0);
+            counts[0] += down_votes;
+            counts[1] += up_votes;
+            self.reaction_counts.insert(&post_id, &counts);
+            self.internal_update_post_score(&post_id, up_votes as i64 - down_votes as i64);
+
+            processed += std::cmp::max(end - start, 1);
+            if end < codes.len() {
+                self.migration.as_mut().unwrap().entry_index = end;
+                break;
+            }
+            self.migration.as_mut().unwrap().entry_index = 0;
+            index += 1;
+        }
+        next_index(index, self.likes.len())
+    }
+
+    fn internal_migrate_followers(&mut self, from_index: u64, limit: u64) -> Option<u64> {
+        let to = std::cmp::min(from_index + limit, self.accounts.len());
+        for index in from_index..to {
+            let account_id = self.accounts.keys_as_vector().get(index).unwrap();
+            let num_followers = self.internal_get_account(&account_id).followers.len();
+            if num_followers > 0 {
+                self.internal_add_reputation(
+                    &account_id,
+                    num_followers as i64 * FOLLOWER_REPUTATION,
+                );
+            }
+        }
+        next_index(to, self.accounts.len())
+    }
+}
+
+fn next_index(to: u64, len: u64) -> Option<u64> {
+    if to < len {
+        Some(to)
+    } else {
+        None
+    }
+}
+
+/// Same collection with another value type, the values are not read.
+fn cast_collection<Old, New>(collection: &Old) -> New
+where
+    Old: BorshSerialize,
+    New: BorshDeserialize,
+{
+    New::try_from_slice(&collection.try_to_vec().unwrap()).unwrap()
+}
+
+/// Rewrites the values of `map` at the positions `from_index..from_index + limit` with the
+/// new layout, keeping its prefix. Returns the next position, `None` at the end.
+fn upgrade_unordered_map<K, Old, New>(
+    map: &UnorderedMap<K, New>,
+    from_index: u64,
+    limit: u64,
+) -> Option<u64>
+where
+    K: BorshSerialize + BorshDeserialize,
+    Old: BorshSerialize + BorshDeserialize,
+    New: BorshSerialize + BorshDeserialize + From<Old>,
+{
+    let mut old_map: UnorderedMap<K, Old> = cast_collection(map);
+    let to = std::cmp::min(from_index + limit, old_map.len());
+    for index in from_index..to {
+        let key = old_map.keys_as_vector().get_raw(index).unwrap();
+        let value = old_map.values_as_vector().get(index).unwrap();
+        old_map.insert_raw(&key, &New::from(value).try_to_vec().unwrap());
+    }
+    next_index(to, old_map.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn ids(posts: &[Post]) -> Vec<PostId> {
+        posts.iter().map(|post| post.id.clone()).collect()
+    }
+
+    fn v1_state() -> ContractV1 {
+        let mut state = ContractV1 {
+            ft_contract: "nft.testnet".to_string(),
+
+            storage_accounts: LookupMap::new(StorageKey::StorageAccount),
+            accounts: UnorderedMap::new(StorageKey::Accounts),
+
+            posts: UnorderedMap::new(StorageKey::Posts),
+            user_posts: LookupMap::new(StorageKey::UserPosts),
+            deleted_posts: UnorderedSet::new(StorageKey::DeletedPosts),
+
+            messages: LookupMap::new(StorageKey::Messages),
+            likes: UnorderedMap::new(StorageKey::Likes),
+            comments: LookupMap::new(StorageKey::Commnets),
+            check_repost: LookupMap::new(StorageKey::CheckRePost),
+
+            topics: UnorderedMap::new(StorageKey::Topics),
+            topics_posts: LookupMap::new(StorageKey::TopicsPosts),
+
+            communities: UnorderedMap::new(StorageKey::Communities),
+            communities_posts: UnorderedMap::new(StorageKey::CommunitiesPosts),
+
+            members_in_communites: UnorderedMap::new(StorageKey::MemberInCommunites),
+            storage_account_in_bytes: 100,
+            admins: LookupSet::new(StorageKey::Admins),
+
+            place_ids: UnorderedSet::new(StorageKey::PlaceIds),
+            chests_per_place: LookupMap::new(StorageKey::ChestsPerPlace),
+            chests: LookupMap::new(StorageKey::Chests),
+        };
+
+        let account_id: AccountId = accounts(1).into();
+        let mut account = AccountV1 {
+            following: UnorderedSet::new(b"ob".to_vec()),
+            followers: UnorderedSet::new(b"ib".to_vec()),
+            chests: vec!["chest_bob".to_string()],
+            bookmarks: vec![],
+            related_conversations: UnorderedSet::new(b"zb".to_vec()),
+            message_pub_key: "".to_string(),
+            avatar: "".to_string(),
+            thumbnail: "".to_string(),
+            display_name: "Bob".to_string(),
+            bio: "".to_string(),
+            joined_communities: UnorderedSet::new(b"jb".to_vec()),
+        };
+        account.followers.insert(&accounts(2).into());
+        state.accounts.insert(&account_id, &VAccount::V1(account));
+        state.storage_accounts.insert(
+            &account_id,
+            &StorageAccount {
+                balance: 10u128.pow(24),
+                used_bytes: 1000,
+            },
+        );
+        state.admins.insert(&account_id);
+
+        let topic = TopicV1 {
+            id: "default".to_string(),
+            admin: accounts(0),
+            name: "Default".to_string(),
+            created_time: 0.into(),
+            description: "".to_string(),
+        };
+        state.topics.insert(&topic.id, &topic);
+
+        let post = PostV1 {
+            id: "1600000000_bob".to_string(),
+            account_id: account_id.clone(),
+            topic,
+            title: "title".to_string(),
+            body: "body".to_string(),
+            post_type: PostType::Website {
+                url: "https://near.org".to_string(),
+                site_id: "near".to_string(),
+            },
+            time: 0.into(),
+            num_quote: 0,
+        };
+        let mut community_posts = UnorderedMap::new(StorageKey::CommunitiesPostsInner {
+            id: "rust0".to_string(),
+        });
+        community_posts.insert(
+            &"1_1600000001_bob".to_string(),
+            &VPost::V1(PostV1 {
+                id: "1_1600000001_bob".to_string(),
+                account_id: account_id.clone(),
+                topic: state.topics.get(&"default".to_string()).unwrap(),
+                title: "title".to_string(),
+                body: "community".to_string(),
+                post_type: PostType::Text,
+                time: 1.into(),
+                num_quote: 0,
+            }),
+        );
+        state
+            .communities_posts
+            .insert(&"rust".to_string(), &community_posts);
+        let mut topics_posts = UnorderedSet::new(StorageKey::TopicsPostsInner {
+            id: account_id.clone(),
+        });
+        topics_posts.insert(&post.id);
+        state.topics_posts.insert(&account_id, &topics_posts);
+        state.posts.insert(&post.id.clone(), &VPost::V1(post));
+
+        let mut comments = Vector::new(b"c1".to_vec());
+        comments.push(&CommentV1 {
+            owner: accounts(2).into(),
+            body: "first".to_string(),
+            time: 1.into(),
+        });
+        comments.push(&CommentV1 {
+            owner: account_id.clone(),
+            body: "second".to_string(),
+            time: 2.into(),
+        });
+        state
+            .comments
+            .insert(&"1600000000_bob".to_string(), &comments);
+
+        state.communities.insert(
+            &"rust".to_string(),
+            &CommunityV1 {
+                id: "rust".to_string(),
+                admin: accounts(1),
+                thumbnail: "".to_string(),
+                avatar: "".to_string(),
+                name: "Rust".to_string(),
+                created_time: 0.into(),
+                description: "".to_string(),
+            },
+        );
+
+        state.chests.insert(
+            &"chest_bob".to_string(),
+            &Chest {
+                id: "chest_bob".to_string(),
+                sender_account_id: account_id,
+                sender_name: "Bob".to_string(),
+                code: "code".to_string(),
+                message: "hello".to_string(),
+                location: Location {
+                    label: "hanoi".to_string(),
+                    lat: 21.0,
+                    lng: 105.8,
+                },
+                time: 0,
+                expired_time: DEFAULT_EXPIRE_TIME,
+                minted: false,
+                chest_type: ChestType::Standard,
+            },
+        );
+        state.place_ids.insert(&"hanoi".to_string());
+
+        state.messages.insert(
+            &"charlie_bob".to_string(),
+            &PrivateMessage {
+                message_id: Some("charlie_bob".to_string()),
+                sender_id: "charlie".to_string(),
+                receiver_id: "bob".to_string(),
+                sender_body: "sender".to_string(),
+                receiver_body: "receiver".to_string(),
+                time: 0,
+                block_height: 1,
+                last_message_height: 0,
+            },
+        );
+
+        state
+    }
+
+    fn migrate_v1_state() -> Contract {
+        testing_env!(context(0, NOW).build());
+        env::state_write(&v1_state());
+        assert_eq!(Contract::internal_state_version(), 1);
+        Contract::migrate()
+    }
+
+    fn v1_post(id: &str, time: u64, state: &ContractV1) -> VPost {
+        VPost::V1(PostV1 {
+            id: id.to_string(),
+            account_id: accounts(1).into(),
+            topic: state.topics.get(&"default".to_string()).unwrap(),
+            title: "title".to_string(),
+            body: "body".to_string(),
+            post_type: PostType::Text,
+            time: time.into(),
+            num_quote: 0,
+        })
+    }
+
+    /// V1 deleted posts by swapping the last post of the store into their place.
+    fn v1_state_with_deleted_posts() -> ContractV1 {
+        let mut state = v1_state();
+        for time in [10, 20, 30, 40].iter() {
+            let id = format!("16000000{}_bob", time);
+            state.posts.insert(&id, &v1_post(&id, *time, &state));
+        }
+        state.posts.remove(&"1600000020_bob".to_string());
+        state.deleted_posts.insert(&"1600000020_bob".to_string());
+
+        let mut community_posts = state.communities_posts.get(&"rust".to_string()).unwrap();
+        for time in [5, 15, 25].iter() {
+            let id = format!("1_16000000{:02}_bob", time);
+            community_posts.insert(&id, &v1_post(&id, *time, &state));
+        }
+        community_posts.remove(&"1_1600000005_bob".to_string());
+        state
+            .communities_posts
+            .insert(&"rust".to_string(), &community_posts);
+        state
+    }
+
+    /// Runs `migrate_step` until the migration finished, returns the number of calls.
+    fn run_migration(contract: &mut Contract, limit: u64) -> u64 {
+        let mut calls = 1;
+        let mut progress = contract.migrate_step(0, limit);
+        while let Some(MigrationProgress { next_index, .. }) = progress {
+            progress = contract.migrate_step(next_index, limit);
+            calls += 1;
+        }
+        calls
+    }
+
+    #[test]
+    fn test_migrate_from_v1() {
+        testing_env!(context(0, NOW).build());
+        env::state_write(&v1_state_with_deleted_posts());
+        let mut contract = Contract::migrate();
+        assert_eq!(contract.get_state_version(), STATE_VERSION);
+        assert_eq!(
+            contract.get_migration_progress(),
+            Some(MigrationProgress {
+                step: MigrationStep::Topics,
+                next_index: 0
+            })
+        );
+        // One call per topic, community, post, community post, removed V1 community post,
+        // comment, live or deleted post without comments and account, and one per empty step.
+        assert_eq!(run_migration(&mut contract, 1), 24);
+        assert_eq!(contract.get_migration_progress(), None);
+
+        assert_eq!(contract.get_owner(), "alice".to_string());
+        assert!(contract.is_admin("bob".to_string()));
+
+        let account = contract.get_account(accounts(1)).unwrap();
+        assert_eq!(account.display_name, "Bob");
+        assert_eq!(account.num_followers, 1);
+        // One follower and one comment of another account.
+        assert_eq!(account.reputation, FOLLOWER_REPUTATION + COMMENT_REPUTATION);
+        assert_eq!(contract.top_users(10)[0].0, "bob");
+        assert_eq!(contract.get_num_accounts(), 1);
+        assert!(contract.get_blocked(accounts(1), 0, 10).is_empty());
+
+        let post = contract.get_post_by_id("1600000000_bob".to_string());
+        assert_eq!(post.account_id, "bob");
+        assert!(contract.topics_posts.get(&"bob".to_string()).is_none());
+        assert_eq!(contract.get_num_posts_of_topic("default".to_string()), 7);
+        // The store holds 0, 10, 40, 30 since 20 was deleted, the index is in time order.
+        let first = contract.get_posts(None, None, 2);
+        let second = contract.get_posts(first.before, None, 2);
+        assert_eq!(ids(&first.posts), vec!["1600000040_bob", "1600000030_bob"]);
+        assert_eq!(ids(&second.posts), vec!["1600000010_bob", "1600000000_bob"]);
+        assert!(second.before.is_none());
+        let by_account = contract.get_posts_by_account(accounts(1), None, None, 10);
+        assert_eq!(
+            ids(&by_account.posts),
+            vec![
+                "1600000040_bob",
+                "1600000030_bob",
+                "1600000010_bob",
+                "1600000000_bob"
+            ]
+        );
+        let topic_feed = contract.get_topic_feed("default".to_string(), None, 3);
+        assert_eq!(
+            ids(&topic_feed.posts),
+            vec!["1600000040_bob", "1600000030_bob", "1_1600000025_bob"]
+        );
+
+        let comments = contract
+            .get_comments("1600000000_bob".to_string(), None, 10, None)
+            .comments;
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0].id, 1);
+        assert_eq!(comments[0].body, "second");
+        assert_eq!(comments[1].id, 0);
+        assert_eq!(comments[1].owner, "charlie");
+        let top = contract
+            .get_comments(
+                "1600000000_bob".to_string(),
+                None,
+                10,
+                Some(CommentSort::Top),
+            )
+            .comments;
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].id, 1);
+
+        assert_eq!(contract.get_communities(0, 10)[0].posts_count, 3);
+        let post = contract.get_post_by_id("1_1600000001_bob".to_string());
+        assert_eq!(post.community_id, Some("rust".to_string()));
+        // The V1 map holds 1, 25, 15 since 5 was deleted.
+        let first = contract.get_community_posts("rust".to_string(), None, None, 2);
+        let second = contract.get_community_posts("rust".to_string(), first.before, None, 2);
+        assert_eq!(
+            ids(&first.posts),
+            vec!["1_1600000025_bob", "1_1600000015_bob"]
+        );
+        assert_eq!(ids(&second.posts), vec!["1_1600000001_bob"]);
+        assert_eq!(contract.get_all_place_id(), vec!["hanoi".to_string()]);
+        assert_eq!(
+            contract.get_chest_by_account(accounts(1))[0].message,
+            "hello"
+        );
+
+        // The V1 message becomes the first one of the history.
+        let conversation_id = "charlie_bob".to_string();
+        assert_eq!(
+            contract.get_num_conversation_messages(conversation_id.clone()),
+            1
+        );
+        testing_env!(context(1, NOW).build());
+        contract.new_message(
+            accounts(2).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+        let messages = contract.get_conversation_messages(conversation_id.clone(), 0, 10);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender_id, "bob");
+        assert_eq!(messages[1].sender_id, "charlie");
+        assert!(contract.messages.get(&conversation_id).is_none());
+        assert_eq!(
+            contract.get_unread_count(conversation_id, "charlie".to_string()),
+            2
+        );
+    }
+
+    #[test]
+    fn test_migrate_post_with_more_entries_than_limit() {
+        testing_env!(context(0, NOW).build());
+        let mut state = v1_state();
+        let post_id = "1600000000_bob".to_string();
+        let mut comments = state.comments.get(&post_id).unwrap();
+        for time in 3..8 {
+            comments.push(&CommentV1 {
+                owner: accounts(2).into(),
+                body: "more".to_string(),
+                time: time.into(),
+            });
+        }
+        state.comments.insert(&post_id, &comments);
+        let mut likes = UnorderedMap::new(b"lb".to_vec());
+        for (index, code) in [(0, 1), (2, 1), (3, 0), (4, 1), (5, 1)].iter() {
+            likes.insert(&accounts(*index).into(), code);
+        }
+        state.likes.insert(&post_id, &likes);
+        env::state_write(&state);
+        let mut contract = Contract::migrate();
+
+        run_migration(&mut contract, 2);
+        let comments = contract
+            .get_comments(post_id.clone(), None, 10, None)
+            .comments;
+        assert_eq!(comments.len(), 7);
+        assert_eq!(comments[0].id, 6);
+        assert_eq!(comments[6].owner, "charlie");
+        let votes = contract.get_votes(post_id);
+        assert_eq!((votes.up_vote, votes.down_vote, votes.score), (4, 1, 3));
+        assert_eq!(
+            contract.get_account(accounts(1)).unwrap().reputation,
+            FOLLOWER_REPUTATION + COMMENT_REPUTATION + 3 * UPVOTE_REPUTATION
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The contract state is being migrated")]
+    fn test_no_writes_during_migration() {
+        let mut contract = migrate_v1_state();
+        contract.migrate_step(0, 1);
+        testing_env!(context(1, NOW).build());
+        contract.follow(accounts(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "The migration resumes from index 0")]
+    fn test_migrate_step_resumes_from_progress() {
+        let mut contract = migrate_v1_state();
+        contract.migrate_step(0, 1);
+        contract.migrate_step(1, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the owner can call this method")]
+    fn test_only_owner_can_migrate() {
+        let mut contract = migrate_v1_state();
+        testing_env!(context(1, NOW).build());
+        contract.migrate_step(0, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "State is already up to date")]
+    fn test_migrate_current_state() {
+        testing_env!(context(0, NOW).build());
+        let contract = Contract::new(accounts(0), "nft.testnet".to_string());
+        env::state_write(&contract);
+        Contract::migrate();
+    }
+}
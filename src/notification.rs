@@ -0,0 +1,392 @@
+use crate::*;
+
+/// Kinds of notifications, an account can opt out of each of them.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "type")]
+pub enum NotificationType {
+    Follow,
+    Comment,
+    Upvote,
+    Repost,
+    Mention,
+    Message,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+#[serde(tag = "type")]
+pub enum NotificationKind {
+    Follow,
+    Comment {
+        post_id: PostId,
+        comment_id: CommentId,
+    },
+    Upvote {
+        post_id: PostId,
+    },
+    Repost {
+        post_id: PostId,
+        repost_id: PostId,
+    },
+    Mention {
+        post_id: PostId,
+    },
+    Message {
+        conversation_id: MessageId,
+    },
+}
+
+impl NotificationKind {
+    pub fn notification_type(&self) -> NotificationType {
+        match self {
+            NotificationKind::Follow => NotificationType::Follow,
+            NotificationKind::Comment { .. } => NotificationType::Comment,
+            NotificationKind::Upvote { .. } => NotificationType::Upvote,
+            NotificationKind::Repost { .. } => NotificationType::Repost,
+            NotificationKind::Mention { .. } => NotificationType::Mention,
+            NotificationKind::Message { .. } => NotificationType::Message,
+        }
+    }
+
+    /// Post of the notifications that can be undone and redone, they are only sent once so
+    /// repeating the action can't flood the queue. `None` for the ones sent every time.
+    fn once_per_post(&self) -> Option<Option<PostId>> {
+        match self {
+            NotificationKind::Follow => Some(None),
+            NotificationKind::Upvote { post_id }
+            | NotificationKind::Repost { post_id, .. }
+            | NotificationKind::Mention { post_id } => Some(Some(post_id.clone())),
+            NotificationKind::Comment { .. } | NotificationKind::Message { .. } => None,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Notification {
+    /// Ids count the notifications of the account, the queue only keeps the last
+    /// `MAX_NOTIFICATIONS` of them.
+    pub id: U64,
+    pub actor_id: AccountId,
+    #[serde(flatten)]
+    pub kind: NotificationKind,
+    pub time: U64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WrappedNotification {
+    #[serde(flatten)]
+    pub notification: Notification,
+    pub read: bool,
+}
+
+/// One page of notifications, newest first.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct NotificationPage {
+    pub notifications: Vec<WrappedNotification>,
+    /// Pass as `cursor` to load older notifications, `None` once the oldest one was reached.
+    pub cursor: Option<U64>,
+    pub unread_count: u64,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Notifications older than `cursor`, or the newest ones without a cursor.
+    pub fn get_notifications(
+        &self,
+        account_id: ValidAccountId,
+        cursor: Option<U64>,
+        limit: u64,
+    ) -> NotificationPage {
+        let account = self
+            .internal_get_account_optional(account_id.as_ref())
+            .expect("Account not found");
+        let notifications = match self.notifications.get(account_id.as_ref()) {
+            Some(notifications) => notifications,
+            None => {
+                return NotificationPage {
+                    notifications: vec![],
+                    cursor: None,
+                    unread_count: 0,
+                }
+            }
+        };
+
+        let oldest = oldest_notification(&account);
+        let end = cursor
+            .map(|cursor| cursor.0.min(account.num_notifications))
+            .unwrap_or(account.num_notifications);
+        let start = end.saturating_sub(limit).max(oldest);
+        let page = (start..end)
+            .rev()
+            .map(|id| WrappedNotification {
+                notification: notifications.get(id % MAX_NOTIFICATIONS).unwrap(),
+                read: id < account.notifications_read,
+            })
+            .collect();
+
+        NotificationPage {
+            notifications: page,
+            cursor: if start > oldest {
+                Some(start.into())
+            } else {
+                None
+            },
+            unread_count: unread_count(&account),
+        }
+    }
+
+    pub fn get_unread_notification_count(&self, account_id: ValidAccountId) -> u64 {
+        self.internal_get_account_optional(account_id.as_ref())
+            .map(|account| unread_count(&account))
+            .unwrap_or(0)
+    }
+
+    /// Marks the notifications up to the id `up_to`, included, as read.
+    pub fn mark_notifications_read(&mut self, up_to: U64) {
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_get_account(&account_id);
+        let read = (up_to.0 + 1).min(account.num_notifications);
+        if read > account.notifications_read {
+            account.notifications_read = read;
+            self.internal_set_account(&account_id, account);
+        }
+    }
+
+    pub fn set_notification_opt_out(&mut self, notification_type: NotificationType, opt_out: bool) {
+        let account_id = env::predecessor_account_id();
+        let storage_update = self.new_storage_update(account_id.clone());
+        let mut account = self.internal_get_account(&account_id);
+        account
+            .notification_opt_outs
+            .retain(|opted_out| *opted_out != notification_type);
+        if opt_out {
+            account.notification_opt_outs.push(notification_type);
+        }
+        self.internal_set_account(&account_id, account);
+        self.finalize_storage_update(storage_update);
+    }
+
+    pub fn get_notification_opt_outs(&self, account_id: ValidAccountId) -> Vec<NotificationType> {
+        self.internal_get_account_optional(account_id.as_ref())
+            .map(|account| account.notification_opt_outs)
+            .unwrap_or_default()
+    }
+}
+
+impl Contract {
+    /// Skipped for self notifications, unregistered accounts, opted out types, actors the
+    /// account blocked or muted and repeated follows, upvotes, reposts and mentions of the
+    /// same actor. The storage is paid by the actor.
+    pub(crate) fn internal_notify(
+        &mut self,
+        account_id: &AccountId,
+        actor_id: &AccountId,
+        kind: NotificationKind,
+    ) {
+        if account_id == actor_id {
+            return;
+        }
+        let mut account = match self.internal_get_account_optional(account_id) {
+            Some(account) => account,
+            None => return,
+        };
+        if account
+            .notification_opt_outs
+            .contains(&kind.notification_type())
+            || account.blocked.contains(actor_id)
+            || account.muted.contains(actor_id)
+        {
+            return;
+        }
+        if let Some(post_id) = kind.once_per_post() {
+            let key = (
+                account_id.clone(),
+                actor_id.clone(),
+                kind.notification_type(),
+                post_id,
+            );
+            if !self.sent_notifications.insert(&key) {
+                return;
+            }
+        }
+
+        let id = account.num_notifications;
+        account.num_notifications += 1;
+        self.internal_set_account(account_id, account);
+
+        let notification = Notification {
+            id: id.into(),
+            actor_id: actor_id.clone(),
+            kind,
+            time: env::block_timestamp().into(),
+        };
+        let mut notifications = self.notifications.get(account_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::NotificationsInner {
+                id: account_id.clone(),
+            })
+        });
+        let slot = id % MAX_NOTIFICATIONS;
+        if slot < notifications.len() {
+            notifications.replace(slot, &notification);
+        } else {
+            notifications.push(&notification);
+        }
+        self.notifications.insert(account_id, &notifications);
+    }
+
+    /// Mentions of private community posts only notify the members.
+    pub(crate) fn internal_notify_mentions(&mut self, post: &Post, previous_title: &str) {
+        for account_id in self.internal_new_mentions(post, previous_title) {
+            if self.internal_can_access_post(post, Some(&account_id)) {
+                self.internal_notify(
+                    &account_id,
+                    &post.account_id,
+                    NotificationKind::Mention {
+                        post_id: post.id.clone(),
+                    },
+                );
+            }
+        }
+    }
+}
+
+fn oldest_notification(account: &Account) -> u64 {
+    account.num_notifications.saturating_sub(MAX_NOTIFICATIONS)
+}
+
+fn unread_count(account: &Account) -> u64 {
+    account.num_notifications - account.notifications_read.max(oldest_notification(account))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn types(page: &NotificationPage) -> Vec<NotificationType> {
+        page.notifications
+            .iter()
+            .map(|wrapped| wrapped.notification.kind.notification_type())
+            .collect()
+    }
+
+    #[test]
+    fn test_notifications_and_read_marker() {
+        let mut contract = setup();
+        testing_env!(context(1, 1_600_000_001).build());
+        let post = contract.post(
+            "hi @charlie".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["default".to_string()],
+        );
+
+        testing_env!(context(2, 1_600_000_002).build());
+        contract.follow(accounts(1));
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.upvote(post.id.clone());
+        contract.repost(post.id.clone(), None);
+        contract.new_message(
+            accounts(1).into(),
+            "sender".to_string(),
+            "receiver".to_string(),
+        );
+
+        let page = contract.get_notifications(accounts(1), None, 3);
+        assert_eq!(
+            types(&page),
+            vec![
+                NotificationType::Message,
+                NotificationType::Repost,
+                NotificationType::Upvote,
+            ]
+        );
+        assert_eq!(page.unread_count, 5);
+        let older = contract.get_notifications(accounts(1), page.cursor, 3);
+        assert_eq!(
+            types(&older),
+            vec![NotificationType::Comment, NotificationType::Follow]
+        );
+        assert!(older.cursor.is_none());
+        assert_eq!(
+            types(&contract.get_notifications(accounts(2), None, 10)),
+            vec![NotificationType::Mention]
+        );
+
+        testing_env!(context(1, 1_600_000_003).build());
+        contract.mark_notifications_read(2.into());
+        assert_eq!(contract.get_unread_notification_count(accounts(1)), 2);
+        let page = contract.get_notifications(accounts(1), None, 10);
+        assert!(!page.notifications[1].read);
+        assert!(page.notifications[2].read);
+    }
+
+    #[test]
+    fn test_notification_opt_out_and_bound() {
+        let mut contract = setup();
+        testing_env!(context(1, 1_600_000_001).build());
+        contract.set_notification_opt_out(NotificationType::Follow, true);
+
+        testing_env!(context(2, 1_600_000_002).build());
+        contract.follow(accounts(1));
+        assert_eq!(contract.get_unread_notification_count(accounts(1)), 0);
+
+        for index in 0..MAX_NOTIFICATIONS + 5 {
+            testing_env!(context(2, 1_600_000_003 + index).block_index(index).build());
+            contract.new_message(
+                accounts(1).into(),
+                "sender".to_string(),
+                "receiver".to_string(),
+            );
+        }
+        let page = contract.get_notifications(accounts(1), None, MAX_NOTIFICATIONS + 5);
+        assert_eq!(page.notifications.len() as u64, MAX_NOTIFICATIONS);
+        assert_eq!(
+            page.notifications[0].notification.id.0,
+            MAX_NOTIFICATIONS + 4
+        );
+        assert_eq!(page.unread_count, MAX_NOTIFICATIONS);
+        assert!(page.cursor.is_none());
+    }
+
+    #[test]
+    fn test_repeated_actions_notify_once() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+
+        testing_env!(context(2, NOW + 1).build());
+        for _ in 0..3 {
+            contract.follow(accounts(1));
+            contract.unfollow(accounts(1).into());
+            contract.upvote(post.id.clone());
+            contract.unvote(post.id.clone());
+        }
+        contract.comment(post.id.clone(), "body".to_string(), None);
+        contract.comment(post.id.clone(), "body".to_string(), None);
+
+        testing_env!(context(1, NOW + 2).build());
+        for title in &["hi @charlie", "hi", "hi @charlie"] {
+            contract.edit_post(post.id.clone(), Some(title.to_string()), None, None);
+        }
+
+        assert_eq!(
+            types(&contract.get_notifications(accounts(1), None, 10)),
+            vec![
+                NotificationType::Comment,
+                NotificationType::Comment,
+                NotificationType::Upvote,
+                NotificationType::Follow,
+            ]
+        );
+        assert_eq!(
+            types(&contract.get_notifications(accounts(2), None, 10)),
+            vec![NotificationType::Mention]
+        );
+    }
+}
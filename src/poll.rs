@@ -0,0 +1,189 @@
+use crate::*;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PollResults {
+    pub options: Vec<String>,
+    pub votes: Vec<u64>,
+    pub total_votes: u64,
+    pub closes_at: U64,
+    pub closed: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// One vote per account, only members can vote on the polls of a community. Reposts of a
+    /// poll are not polls themselves, votes go to the original post.
+    pub fn vote_poll(&mut self, post_id: PostId, option_index: u32) {
+        let account_id = env::predecessor_account_id();
+        let post = self.internal_get_post(&post_id).expect("Post not found");
+        let (options, closes_at) = match post.post_type.clone() {
+            PostType::Poll { options, closes_at } if post.repost_of.is_none() => {
+                (options, closes_at)
+            }
+            _ => env::panic(b"This post is not a poll"),
+        };
+        assert!(env::block_timestamp() < closes_at.0, "This poll is closed");
+        assert!((option_index as usize) < options.len(), "Option not found");
+        if let Some(community_id) = &post.community_id {
+            let members = self
+                .members_in_communites
+                .get(community_id)
+                .expect("Community not found");
+            assert!(
+                members.contains(&account_id),
+                "Only members of this community can vote"
+            );
+        }
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let mut votes = self.poll_votes.get(&post_id).unwrap_or_else(|| {
+            UnorderedMap::new(StorageKey::PollVotesInner {
+                id: post_id.clone(),
+            })
+        });
+        assert!(
+            votes.insert(&account_id, &option_index).is_none(),
+            "You already voted on this poll"
+        );
+        self.poll_votes.insert(&post_id, &votes);
+
+        let mut tallies = self
+            .poll_tallies
+            .get(&post_id)
+            .unwrap_or_else(|| vec![0; options.len()]);
+        tallies[option_index as usize] += 1;
+        self.poll_tallies.insert(&post_id, &tallies);
+        self.finalize_storage_update(storage_update);
+
+        Event::PollVote(PollVoteData {
+            post_id: &post_id,
+            account_id: &account_id,
+            option_index,
+        })
+        .emit();
+    }
+
+    /// Results of the original poll when `post_id` is a repost of it.
+    pub fn get_poll_results(&self, post_id: PostId) -> PollResults {
+        let mut post = self.internal_get_post(&post_id).expect("Post not found");
+        if let Some(original_post_id) = &post.repost_of {
+            post = self
+                .internal_get_post(original_post_id)
+                .expect("Original post not found");
+        }
+        let (options, closes_at) = match post.post_type {
+            PostType::Poll { options, closes_at } => (options, closes_at),
+            _ => env::panic(b"This post is not a poll"),
+        };
+        let votes = self
+            .poll_tallies
+            .get(&post.id)
+            .unwrap_or_else(|| vec![0; options.len()]);
+
+        PollResults {
+            total_votes: votes.iter().sum(),
+            options,
+            votes,
+            closes_at,
+            closed: env::block_timestamp() >= closes_at.0,
+        }
+    }
+
+    /// Index of the option picked by `account_id`, if they voted, on the original poll when
+    /// `post_id` is a repost of it.
+    pub fn get_poll_vote(&self, post_id: PostId, account_id: AccountId) -> Option<u32> {
+        let post_id = self
+            .internal_get_post(&post_id)
+            .and_then(|post| post.repost_of)
+            .unwrap_or(post_id);
+        self.poll_votes
+            .get(&post_id)
+            .and_then(|votes| votes.get(&account_id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_poll() -> (Contract, Post) {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        let post = contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Poll {
+                options: vec!["yes".to_string(), "no".to_string()],
+                closes_at: ((NOW + 60) * 1_000_000_000).into(),
+            },
+            vec!["default".to_string()],
+        );
+        (contract, post)
+    }
+
+    #[test]
+    fn test_poll_tallies() {
+        let (mut contract, post) = setup_poll();
+
+        contract.vote_poll(post.id.clone(), 0);
+        testing_env!(context(2, NOW + 1).build());
+        contract.vote_poll(post.id.clone(), 1);
+        testing_env!(context(3, NOW + 2).build());
+        contract.vote_poll(post.id.clone(), 1);
+
+        let results = contract.get_poll_results(post.id.clone());
+        assert_eq!(results.votes, vec![1, 2]);
+        assert_eq!(results.total_votes, 3);
+        assert!(!results.closed);
+        assert_eq!(
+            contract.get_poll_vote(post.id.clone(), accounts(2).into()),
+            Some(1)
+        );
+
+        testing_env!(context(3, NOW + 60).build());
+        assert!(contract.get_poll_results(post.id).closed);
+    }
+
+    #[test]
+    fn test_repost_shows_original_poll() {
+        let (mut contract, post) = setup_poll();
+        contract.vote_poll(post.id.clone(), 1);
+        testing_env!(context(2, NOW + 1).build());
+        let repost = contract.repost(post.id.clone(), None);
+
+        let results = contract.get_poll_results(repost.id.clone());
+        assert_eq!(results.votes, vec![0, 1]);
+        assert_eq!(results.total_votes, 1);
+        assert_eq!(
+            contract.get_poll_vote(repost.id, accounts(1).into()),
+            Some(1)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "This post is not a poll")]
+    fn test_poll_rejects_votes_on_reposts() {
+        let (mut contract, post) = setup_poll();
+        testing_env!(context(2, NOW + 1).build());
+        let repost = contract.repost(post.id, None);
+        contract.vote_poll(repost.id, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "You already voted on this poll")]
+    fn test_poll_one_vote_per_account() {
+        let (mut contract, post) = setup_poll();
+        contract.vote_poll(post.id.clone(), 0);
+        contract.vote_poll(post.id, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "This poll is closed")]
+    fn test_poll_rejects_votes_after_close() {
+        let (mut contract, post) = setup_poll();
+        testing_env!(context(2, NOW + 60).build());
+        contract.vote_poll(post.id, 0);
+    }
+}
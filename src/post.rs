@@ -7,7 +7,73 @@ pub type TokenId = String;
 #[serde(crate = "near_sdk::serde")]
 #[serde(tag = "type")]
 pub enum PostType {
-    Website { url: String, site_id: String }
+    Website {
+        url: String,
+        site_id: String,
+    },
+    Text,
+    Image {
+        ipfs_cid: String,
+    },
+    Video {
+        url: String,
+    },
+    Link {
+        url: String,
+    },
+    Gallery {
+        cids: Vec<String>,
+    },
+    /// `closes_at` is a block timestamp in nanoseconds, votes are rejected from then on.
+    Poll {
+        options: Vec<String>,
+        closes_at: U64,
+    },
+}
+
+impl PostType {
+    /// Shared by every method that writes a post.
+    pub(crate) fn assert_valid(&self) {
+        match self {
+            PostType::Website { url, .. } | PostType::Video { url } | PostType::Link { url } => {
+                assert!(valid_url(url.clone()), "Not valid url")
+            }
+            PostType::Text => {}
+            PostType::Image { ipfs_cid } => assert!(
+                ipfs_cid.len() == IPFS_HASH_LENGTH,
+                "Image should be an ipfs hash!!"
+            ),
+            PostType::Gallery { cids } => {
+                assert!(
+                    !cids.is_empty() && cids.len() <= MAX_GALLERY_SIZE,
+                    "Gallery should have between 1 and {} images",
+                    MAX_GALLERY_SIZE
+                );
+                assert!(
+                    cids.iter().all(|cid| cid.len() == IPFS_HASH_LENGTH),
+                    "Image should be an ipfs hash!!"
+                );
+            }
+            PostType::Poll { options, closes_at } => {
+                assert!(
+                    options.len() >= 2 && options.len() <= MAX_POLL_OPTIONS,
+                    "Poll should have between 2 and {} options",
+                    MAX_POLL_OPTIONS
+                );
+                assert!(
+                    options
+                        .iter()
+                        .all(|option| !option.is_empty() && option.len() <= MAX_POLL_OPTION_LENGTH),
+                    "Poll option should have between 1 and {} characters",
+                    MAX_POLL_OPTION_LENGTH
+                );
+                assert!(
+                    closes_at.0 > env::block_timestamp(),
+                    "Poll should close in the future"
+                );
+            }
+        }
+    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
@@ -15,12 +81,25 @@ pub enum PostType {
 pub struct Post {
     pub id: PostId,
     pub account_id: AccountId,
-    pub topic: Topic,
+    pub topics: Vec<TopicId>,
     pub title: String,
     pub body: String,
     pub post_type: PostType,
     pub time: U64,
     pub num_quote: u32,
+    pub repost_of: Option<PostId>,
+    pub community_id: Option<CommunityId>,
+    pub edited_at: Option<U64>,
+}
+
+/// Content of a post before an edit, `time` is when that content was written.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PostRevision {
+    pub title: String,
+    pub body: String,
+    pub post_type: PostType,
+    pub time: U64,
 }
 
 #[derive(Serialize)]
@@ -28,11 +107,13 @@ pub struct Post {
 pub struct PostStats {
     pub num_likes: i64,
     pub post_id: PostId,
+    pub community_id: Option<CommunityId>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[serde(crate = "near_sdk::serde")]
 pub enum VPost {
+    V1(PostV1),
     Last(Post),
 }
 
@@ -45,6 +126,7 @@ impl From<Post> for VPost {
 impl From<VPost> for Post {
     fn from(v_post: VPost) -> Self {
         match v_post {
+            VPost::V1(post) => post.into(),
             VPost::Last(post) => post,
         }
     }
@@ -57,9 +139,9 @@ impl Contract {
         title: String,
         body: String,
         post_type: PostType,
-        topic_id: TopicId,
+        topic_ids: Vec<TopicId>,
     ) -> Post {
-        assert!(self.topics.get(&topic_id).is_some(), "Not found your topic");
+        self.assert_post_topics(&topic_ids);
 
         assert!(
             title.len() <= MAX_TITLE_LENGTH,
@@ -72,9 +154,7 @@ impl Contract {
             "Body should be an ipfs hash!!",
         );
 
-        match post_type.clone() {
-            PostType::Website { url, site_id } => assert!(valid_url(url), "Not valid url")
-        };
+        post_type.assert_valid();
 
         let account_id = env::predecessor_account_id();
         let storage_update = self.new_storage_update(account_id.clone());
@@ -90,74 +170,305 @@ impl Contract {
             body,
             post_type,
             time: env::block_timestamp().into(),
-            topic: self.topics.get(&topic_id).unwrap(),
+            topics: topic_ids,
             num_quote: 0,
+            repost_of: None,
+            community_id: None,
+            edited_at: None,
         };
 
-        let v_post = post.into();
-        self.posts.insert(&post_id, &v_post);
+        let post = self.internal_insert_post(post);
 
-        //Insert to user posts
-        let mut user_posts = self
-            .user_posts
-            .get(&env::predecessor_account_id())
-            .unwrap_or_else(|| {
-                UnorderedSet::new(StorageKey::UserPostsInner {
-                    id: env::predecessor_account_id(),
-                })
-            });
+        self.internal_set_account(&account_id, account);
+        self.internal_notify_mentions(&post, "");
+        self.finalize_storage_update(storage_update);
 
-        user_posts.insert(&post_id);
-        self.user_posts
-            .insert(&env::predecessor_account_id(), &user_posts);
+        Event::PostCreate(PostData {
+            post_id: &post_id,
+            account_id: &account_id,
+            community_id: None,
+        })
+        .emit();
+        post
+    }
 
-        //Insert to Topic posts
-        let mut topics_posts = self.topics_posts.get(&account_id).unwrap_or_else(|| {
-            UnorderedSet::new(StorageKey::TopicsPostsInner {
-                id: account_id.clone(),
+    /// Creates a new post referencing `original_post_id`, with an optional quote body. The
+    /// repost is listed in the topics of the original, so posts of archived topics can't be
+    /// reposted.
+    pub fn repost(&mut self, original_post_id: PostId, body: Option<String>) -> Post {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.can_repost(account_id.clone(), original_post_id.clone()),
+            "You already reposted this post"
+        );
+
+        if let Some(body) = &body {
+            assert!(
+                body.len() == IPFS_HASH_LENGTH,
+                "Body should be an ipfs hash!!",
+            );
+        }
+
+        let mut original = self
+            .internal_get_post(&original_post_id)
+            .expect("Post not found");
+        assert!(original.repost_of.is_none(), "Can not repost a repost");
+        if let Some(community_id) = &original.community_id {
+            assert!(
+                self.internal_can_access_community(community_id, None),
+                "Can not repost a post of a private community"
+            );
+        }
+        self.assert_post_topics(&original.topics);
+
+        let storage_update = self.new_storage_update(account_id.clone());
+
+        let block_timestamp = env::block_timestamp() / 1_000_000_000;
+        let post_id = block_timestamp.to_string() + "_" + &account_id;
+
+        let post = Post {
+            id: post_id.clone(),
+            account_id: account_id.clone(),
+            title: "".to_string(),
+            body: body.unwrap_or_default(),
+            post_type: original.post_type.clone(),
+            time: env::block_timestamp().into(),
+            topics: original.topics.clone(),
+            num_quote: 0,
+            repost_of: Some(original_post_id.clone()),
+            community_id: None,
+            edited_at: None,
+        };
+
+        let post = self.internal_insert_post(post);
+
+        let mut reposts = self.check_repost.get(&original_post_id).unwrap_or_else(|| {
+            UnorderedMap::new(StorageKey::CheckRePostInner {
+                id: original_post_id.clone(),
             })
         });
+        reposts.insert(&account_id, &post_id);
+        self.check_repost.insert(&original_post_id, &reposts);
 
-        topics_posts.insert(&post_id);
-        self.topics_posts.insert(&account_id.clone(), &topics_posts);
+        original.num_quote += 1;
+        let original_account_id = original.account_id.clone();
+        self.posts.insert(&original_post_id, &original.into());
+        self.internal_notify(
+            &original_account_id,
+            &account_id,
+            NotificationKind::Repost {
+                post_id: original_post_id.clone(),
+                repost_id: post_id.clone(),
+            },
+        );
 
-        self.internal_set_account(&account_id, account);
         self.finalize_storage_update(storage_update);
+
+        Event::Repost(RepostData {
+            post_id: &post_id,
+            original_post_id: &original_post_id,
+            account_id: &account_id,
+        })
+        .emit();
+        post
+    }
+
+    /// Author only. The previous content is kept in the revision history, a post can be
+    /// edited `MAX_POST_REVISIONS` times.
+    pub fn edit_post(
+        &mut self,
+        post_id: PostId,
+        title: Option<String>,
+        body: Option<String>,
+        post_type: Option<PostType>,
+    ) -> Post {
+        let account_id = env::predecessor_account_id();
+        let mut post = self.internal_get_post(&post_id).expect("Post not found");
+        let community_id = post.community_id.clone();
+        assert!(
+            post.account_id == account_id,
+            "You are not the owner of this post"
+        );
+        assert!(
+            post.repost_of.is_none() || (title.is_none() && post_type.is_none()),
+            "Only the quote of a repost can be edited"
+        );
+        assert!(
+            post_type.is_none()
+                || !matches!(post.post_type, PostType::Poll { .. })
+                    && !matches!(post_type, Some(PostType::Poll { .. })),
+            "Poll options can not be edited"
+        );
+
+        if let Some(title) = &title {
+            assert!(
+                title.len() <= MAX_TITLE_LENGTH,
+                "Can not make a post title more than {} characters",
+                MAX_TITLE_LENGTH
+            );
+        }
+        if let Some(body) = &body {
+            if community_id.is_some() {
+                assert!(
+                    body.len() <= MAX_BODY_LENGTH,
+                    "Can not make a post body more than {} characters",
+                    MAX_BODY_LENGTH
+                );
+            } else {
+                assert!(
+                    body.len() == IPFS_HASH_LENGTH,
+                    "Body should be an ipfs hash!!",
+                );
+            }
+        }
+        if let Some(post_type) = &post_type {
+            post_type.assert_valid();
+        }
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        let mut revisions = self.post_revisions.get(&post_id).unwrap_or_else(|| {
+            Vector::new(StorageKey::PostRevisionsInner {
+                id: post_id.clone(),
+            })
+        });
+        assert!(
+            revisions.len() < MAX_POST_REVISIONS,
+            "Can not edit a post more than {} times",
+            MAX_POST_REVISIONS
+        );
+        revisions.push(&PostRevision {
+            title: post.title.clone(),
+            body: post.body.clone(),
+            post_type: post.post_type.clone(),
+            time: post.edited_at.unwrap_or(post.time),
+        });
+        self.post_revisions.insert(&post_id, &revisions);
+
+        if let Some(body) = body {
+            post.body = body;
+        }
+        if let Some(post_type) = post_type {
+            post.post_type = post_type;
+        }
+        if let Some(title) = title {
+            let previous_title = std::mem::replace(&mut post.title, title);
+            self.internal_index_post_tags(&post, &previous_title);
+            self.internal_notify_mentions(&post, &previous_title);
+        }
+        post.edited_at = Some(env::block_timestamp().into());
+
+        let v_post = post.into();
+        self.posts.insert(&post_id, &v_post);
+        self.finalize_storage_update(storage_update);
+
+        Event::PostEdit(PostData {
+            post_id: &post_id,
+            account_id: &account_id,
+            community_id: community_id.as_deref(),
+        })
+        .emit();
         v_post.into()
     }
 
+    /// Previous contents of the post, oldest first.
+    pub fn get_post_revisions(
+        &self,
+        post_id: PostId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<PostRevision> {
+        match self.post_revisions.get(&post_id) {
+            Some(revisions) => (from_index..std::cmp::min(from_index + limit, revisions.len()))
+                .map(|index| revisions.get(index).unwrap())
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    /// Moderators of a community can also delete the posts made in it.
     pub fn delete_post(&mut self, post_id: PostId) {
-        let owner = env::predecessor_account_id();
-        let mut user_post = self
-            .user_posts
-            .get(&owner)
-            .expect("User doesn't have posts!");
+        self.assert_migrated();
+        let account_id = env::predecessor_account_id();
+        let post = self.internal_get_post(&post_id).expect("Post not found");
 
         assert!(
-            user_post.contains(&post_id) || self.is_admin(owner.clone()),
-            "You are not the owner of this post"
+            post.account_id == account_id
+                || self.has_role(account_id.clone(), Role::Moderator)
+                || post
+                    .community_id
+                    .as_ref()
+                    .map(|community_id| {
+                        self.is_community_moderator(community_id.clone(), account_id.clone())
+                    })
+                    .unwrap_or(false),
+            "You don't have permission to delete"
         );
 
         //Delete this post
         self.posts.remove(&post_id);
-        user_post.remove(&post_id);
-        self.user_posts.insert(&owner, &user_post);
+        match &post.community_id {
+            Some(community_id) => {
+                let mut community_posts = self.community_posts.get(community_id).unwrap();
+                community_posts.remove(&post_id);
+                self.community_posts.insert(community_id, &community_posts);
+            }
+            None => {
+                let mut user_post = self
+                    .user_posts
+                    .get(&post.account_id)
+                    .expect("User doesn't have posts!");
+                user_post.remove(&post_id);
+                self.user_posts.insert(&post.account_id, &user_post);
+            }
+        }
+
+        self.internal_remove_topic_post(&post);
 
         //Add post id to list
         self.deleted_posts.insert(&post_id);
-    }
+        self.internal_unrank_post(&post);
 
-    //TODO: paging
-    pub fn get_posts_by_account(&self, account_id: ValidAccountId) -> Vec<Post> {
-        if let Some(posts) = self.user_posts.get(&account_id.into()) {
-            return posts
-                .iter()
-                .map(|post_id| self.posts.get(&post_id).unwrap().into())
-                .collect();
+        if let Some(original_post_id) = &post.repost_of {
+            self.internal_remove_repost(original_post_id, &post.account_id);
+
+            Event::RepostDelete(RepostData {
+                post_id: &post_id,
+                original_post_id,
+                account_id: &post.account_id,
+            })
+            .emit();
         }
-        vec![]
+
+        Event::PostDelete(PostData {
+            post_id: &post_id,
+            account_id: &account_id,
+            community_id: post.community_id.as_deref(),
+        })
+        .emit();
+    }
+
+    /// Posts of the account outside communities, see `get_community_posts` for the others.
+    pub fn get_posts_by_account(
+        &self,
+        account_id: ValidAccountId,
+        before: Option<U64>,
+        after: Option<U64>,
+        limit: u64,
+    ) -> PostPage {
+        let index = self
+            .account_post_index
+            .get(account_id.as_ref())
+            .unwrap_or_else(|| {
+                Vector::new(StorageKey::AccountPostIndexInner {
+                    id: account_id.into(),
+                })
+            });
+        page_post_index(&index, before, after, limit, |post_id| {
+            self.posts.get(post_id).map(Into::into)
+        })
     }
 
+    /// Posts of the account outside communities.
     pub fn get_num_posts_by_account(&self, account_id: ValidAccountId) -> u64 {
         if let Some(posts) = self.user_posts.get(&account_id.into()) {
             return posts.len();
@@ -165,76 +476,25 @@ impl Contract {
         0
     }
 
-    pub fn get_posts_of_topic(&self, topic_id: TopicId) -> Vec<PostId> {
-        if let Some(posts) = self.topics_posts.get(&topic_id) {
-            return posts.to_vec();
-        }
-        vec![]
-    }
-
-    pub fn get_posts(&self, from_index: u64, limit: u64) -> Vec<Post> {
-        let posts = self.posts.keys_as_vector();
-        let from = if posts.len() > (limit + from_index) {
-            posts.len() - limit - from_index
-        } else {
-            0
-        };
-
-        let to = if posts.len() > from_index {
-            posts.len() - from_index
-        } else {
-            0
-        };
-
-        (from..to)
-            .map(|index| {
-                let post_id = posts.get(index).unwrap();
-                self.posts.get(&post_id).unwrap().into()
-            })
-            .rev()
-            .collect()
+    /// Latest posts outside communities, pass a cursor of the previous page to load older or
+    /// newer ones. Community posts are only listed by `get_community_posts`.
+    pub fn get_posts(&self, before: Option<U64>, after: Option<U64>, limit: u64) -> PostPage {
+        page_post_index(&self.post_index, before, after, limit, |post_id| {
+            self.posts.get(post_id).map(Into::into)
+        })
     }
 
     pub fn get_post_by_id(&self, post_id: PostId) -> Post {
-        self.posts.get(&post_id).unwrap().into()
+        self.internal_get_post(&post_id).expect("Post not found")
     }
 
     pub fn get_post_by_ids(&self, post_ids: Vec<PostId>) -> Vec<Post> {
         post_ids
             .iter()
-            .map(|post_id| self.posts.get(post_id).unwrap().into())
+            .map(|post_id| self.internal_get_post(post_id).expect("Post not found"))
             .collect()
     }
 
-    pub fn get_hot_posts(&self) -> Vec<PostStats> {
-        self.posts_with_filter(ONE_DAY_UNIX_TIME)
-    }
-
-    pub fn get_trending_posts(&self) -> Vec<PostStats> {
-        self.posts_with_filter(ONE_WEEK_UNIX_TIME)
-    }
-
-    fn posts_with_filter(&self, filter_duration: u64) -> Vec<PostStats> {
-        let time_to_filter = env::block_timestamp() / 1_000_000_000 - filter_duration;
-        let mut un_sorted_vec: Vec<PostStats> = self
-            .likes
-            .keys()
-            .filter(|k| {
-                env::log(k.to_string().as_bytes());
-                let id_splited: Vec<&str> = k.split('_').collect();
-
-                let timestamp = id_splited[0].parse::<u64>().ok().unwrap();
-                timestamp > time_to_filter
-            })
-            .map(|k| PostStats {
-                post_id: k.clone(),
-                num_likes: self.get_votes(k),
-            })
-            .collect();
-
-        un_sorted_vec.sort_by(|a, b| b.num_likes.cmp(&a.num_likes));
-        un_sorted_vec
-    }
     pub fn test(&self) -> Vec<String> {
         self.likes.keys_as_vector().to_vec()
     }
@@ -242,28 +502,414 @@ impl Contract {
     //Repost functions
     pub fn can_repost(&self, account_id: AccountId, post_id: PostId) -> bool {
         if let Some(reposts) = self.check_repost.get(&post_id) {
-            return !reposts.contains(&account_id);
+            return reposts.get(&account_id).is_none();
         }
         true
     }
 
-    pub fn undo_repost(&mut self, original_post_id: PostId, repost_id: PostId) {
-        assert!(
-            !self.can_repost(env::predecessor_account_id(), original_post_id.clone()),
-            "This post is not rerepped by your account!"
-        );
+    pub fn undo_repost(&mut self, original_post_id: PostId) {
+        self.assert_migrated();
+        let repost_id = self
+            .check_repost
+            .get(&original_post_id)
+            .and_then(|reposts| reposts.get(&env::predecessor_account_id()))
+            .expect("This post is not rerepped by your account!");
 
-        let mut reposts = self.check_repost.get(&original_post_id).unwrap();
-        reposts.remove(&env::predecessor_account_id());
-        self.check_repost.insert(&original_post_id, &reposts);
         self.delete_post(repost_id);
-
     }
 
     pub fn repost_count(&self, post_id: PostId) -> u64 {
         if let Some(reposts) = self.check_repost.get(&post_id) {
             return reposts.len();
         }
-        0 
+        0
+    }
+
+    pub fn get_reposts_of(&self, post_id: PostId, from_index: u64, limit: u64) -> Vec<Post> {
+        match self.check_repost.get(&post_id) {
+            Some(reposts) => {
+                let repost_ids = reposts.values_as_vector();
+                calculate_rev_limit(repost_ids.len(), from_index, limit)
+                    .map(|index| {
+                        let repost_id = repost_ids.get(index).unwrap();
+                        self.posts.get(&repost_id).unwrap().into()
+                    })
+                    .rev()
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+}
+
+impl Contract {
+    pub(crate) fn internal_get_post(&self, post_id: &PostId) -> Option<Post> {
+        self.posts.get(post_id).map(Into::into)
+    }
+
+    /// See `internal_can_access_community`.
+    pub(crate) fn internal_can_access_post(
+        &self,
+        post: &Post,
+        account_id: Option<&AccountId>,
+    ) -> bool {
+        post.community_id
+            .as_ref()
+            .map(|community_id| self.internal_can_access_community(community_id, account_id))
+            .unwrap_or(true)
+    }
+
+    /// Comments and votes need access to the post and are rejected for banned accounts.
+    pub(crate) fn assert_can_interact_with_post(&self, post: &Post, account_id: &AccountId) {
+        if let Some(community_id) = &post.community_id {
+            self.assert_not_banned(community_id, account_id);
+        }
+        assert!(
+            self.internal_can_access_post(post, Some(account_id)),
+            "This community is private"
+        );
+    }
+
+    /// Community posts are only listed in the index of their community.
+    pub(crate) fn internal_insert_post(&mut self, post: Post) -> Post {
+        let account_id = post.account_id.clone();
+        let post_id = post.id.clone();
+        let v_post = post.into();
+        assert!(
+            self.posts.insert(&post_id, &v_post).is_none(),
+            "Post already exists"
+        );
+
+        let post: Post = v_post.into();
+        self.internal_insert_topic_post(&post);
+        self.internal_index_post_tags(&post, "");
+
+        if let Some(community_id) = &post.community_id {
+            let mut community_posts = self.community_posts.get(community_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::CommunityPostsInner {
+                    id: community_id.clone(),
+                })
+            });
+            community_posts.insert(&post_id);
+            self.community_posts.insert(community_id, &community_posts);
+            self.internal_index_community_post(community_id, &post);
+            return post;
+        }
+
+        //Insert to user posts
+        let mut user_posts = self.user_posts.get(&account_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::UserPostsInner {
+                id: account_id.clone(),
+            })
+        });
+
+        user_posts.insert(&post_id);
+        self.user_posts.insert(&account_id, &user_posts);
+
+        self.internal_index_post(&post);
+
+        post
+    }
+
+    fn internal_remove_repost(&mut self, original_post_id: &PostId, account_id: &AccountId) {
+        if let Some(mut reposts) = self.check_repost.get(original_post_id) {
+            reposts.remove(account_id);
+            self.check_repost.insert(original_post_id, &reposts);
+        }
+
+        if let Some(original) = self.posts.get(original_post_id) {
+            let mut original: Post = original.into();
+            original.num_quote = original.num_quote.saturating_sub(1);
+            self.posts.insert(original_post_id, &original.into());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn nanos(seconds: u64) -> U64 {
+        (seconds * 1_000_000_000).into()
+    }
+
+    fn edit_title(contract: &mut Contract, post_id: &PostId, title: &str, seconds: u64) -> Post {
+        testing_env!(context(1, seconds).build());
+        contract.edit_post(post_id.clone(), Some(title.to_string()), None, None)
+    }
+
+    #[test]
+    fn test_edit_post_keeps_revisions() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        let edited = edit_title(&mut contract, &post.id, "first edit", NOW + 1);
+        assert_eq!(edited.title, "first edit");
+        assert_eq!(edited.body, IPFS_BODY);
+        assert_eq!(edited.time, post.time);
+        assert_eq!(edited.edited_at, Some(nanos(NOW + 1)));
+
+        testing_env!(context(1, NOW + 2).build());
+        let body = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdH".to_string();
+        contract.edit_post(post.id.clone(), None, Some(body.clone()), None);
+        assert_eq!(contract.get_post_by_id(post.id.clone()).body, body);
+
+        let revisions = contract.get_post_revisions(post.id.clone(), 0, 10);
+        assert_eq!(revisions.len(), 2);
+        assert_eq!(revisions[0].title, "title");
+        assert_eq!(revisions[0].time, post.time);
+        assert_eq!(revisions[1].title, "first edit");
+        assert_eq!(revisions[1].time, nanos(NOW + 1));
+
+        let page = contract.get_post_revisions(post.id.clone(), 1, 10);
+        assert_eq!(page.len(), 1);
+        assert_eq!(page[0].title, "first edit");
+        assert!(contract.get_post_revisions(post.id, 2, 10).is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can not edit a post more than 20 times")]
+    fn test_revisions_are_bounded() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        for index in 0..=MAX_POST_REVISIONS {
+            edit_title(
+                &mut contract,
+                &post.id,
+                &format!("edit {}", index),
+                NOW + index,
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "You are not the owner of this post")]
+    fn test_only_author_edits() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        testing_env!(context(2, NOW).build());
+        contract.edit_post(post.id, Some("edited".to_string()), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Only the quote of a repost can be edited")]
+    fn test_repost_title_can_not_be_edited() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 2, NOW);
+        testing_env!(context(1, NOW + 1).build());
+        let repost = contract.repost(post.id, None);
+        contract.edit_post(repost.id, Some("edited".to_string()), None, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "Body should be an ipfs hash!!")]
+    fn test_edited_body_must_be_an_ipfs_hash() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.edit_post(post.id, None, Some("plain text".to_string()), None);
+    }
+
+    fn post_with_type(contract: &mut Contract, post_type: PostType) -> Post {
+        testing_env!(context(1, NOW).build());
+        contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            post_type,
+            vec!["default".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_post_types() {
+        let mut contract = setup();
+        let url = "https://near.org".to_string();
+        for post_type in [
+            PostType::Image {
+                ipfs_cid: IPFS_BODY.to_string(),
+            },
+            PostType::Video { url: url.clone() },
+            PostType::Link { url: url.clone() },
+            PostType::Gallery {
+                cids: vec![IPFS_BODY.to_string(); MAX_GALLERY_SIZE],
+            },
+            PostType::Website {
+                url,
+                site_id: "near".to_string(),
+            },
+        ] {
+            post_type.assert_valid();
+        }
+
+        let post = post_with_type(
+            &mut contract,
+            PostType::Gallery {
+                cids: vec![IPFS_BODY.to_string(); 2],
+            },
+        );
+        match contract.get_post_by_id(post.id).post_type {
+            PostType::Gallery { cids } => assert_eq!(cids.len(), 2),
+            _ => panic!("Expected a gallery"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "Not valid url")]
+    fn test_video_needs_a_url() {
+        let mut contract = setup();
+        post_with_type(
+            &mut contract,
+            PostType::Video {
+                url: "not a url".to_string(),
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Not valid url")]
+    fn test_link_needs_a_url() {
+        PostType::Link {
+            url: "near.org".to_string(),
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Image should be an ipfs hash!!")]
+    fn test_image_needs_a_cid() {
+        let mut contract = setup();
+        post_with_type(
+            &mut contract,
+            PostType::Image {
+                ipfs_cid: IPFS_BODY[1..].to_string(),
+            },
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Gallery should have between 1 and 10 images")]
+    fn test_gallery_can_not_be_empty() {
+        PostType::Gallery { cids: vec![] }.assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Gallery should have between 1 and 10 images")]
+    fn test_gallery_size_is_bounded() {
+        PostType::Gallery {
+            cids: vec![IPFS_BODY.to_string(); MAX_GALLERY_SIZE + 1],
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Image should be an ipfs hash!!")]
+    fn test_gallery_needs_cids() {
+        PostType::Gallery {
+            cids: vec![IPFS_BODY.to_string(), "cid".to_string()],
+        }
+        .assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "Image should be an ipfs hash!!")]
+    fn test_edited_post_type_is_validated() {
+        let mut contract = setup();
+        let post = new_post(&mut contract, 1, NOW);
+        contract.edit_post(
+            post.id,
+            None,
+            None,
+            Some(PostType::Image {
+                ipfs_cid: "cid".to_string(),
+            }),
+        );
+    }
+
+    #[test]
+    fn test_community_posts_share_the_post_store() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 1, "Rust", CommunityVisibility::Public);
+        let post = new_community_post(&mut contract, 1, NOW, &community_id);
+
+        testing_env!(context(2, NOW).build());
+        contract.join_community(community_id.clone());
+        contract.upvote(post.id.clone());
+        contract.comment(post.id.clone(), "body".to_string(), None);
+
+        let hot = contract.get_hot_posts(0, 10, None);
+        assert_eq!(hot[0].post_id, post.id);
+        assert_eq!(hot[0].community_id, Some(community_id.clone()));
+        assert_eq!(
+            contract.get_post_by_id(post.id.clone()).community_id,
+            Some(community_id.clone())
+        );
+        assert_eq!(
+            contract
+                .get_community_posts(community_id.clone(), None, None, 10)
+                .posts
+                .len(),
+            1
+        );
+        assert!(contract.get_posts(None, None, 10).posts.is_empty());
+        assert!(contract
+            .get_posts_by_account(accounts(1), None, None, 10)
+            .posts
+            .is_empty());
+
+        testing_env!(context(1, NOW).build());
+        contract.delete_community_post(post.id.clone(), community_id);
+        assert!(contract.get_hot_posts(0, 10, None).is_empty());
+        assert_eq!(contract.get_communities(0, 10)[0].posts_count, 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Post not found")]
+    fn test_votes_need_a_post() {
+        let mut contract = setup();
+        contract.upvote("missing".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "This community is private")]
+    fn test_votes_need_access_to_the_community() {
+        let mut contract = setup();
+        let community_id =
+            new_community(&mut contract, 1, "Rust", CommunityVisibility::RequestToJoin);
+        let post = new_community_post(&mut contract, 1, NOW, &community_id);
+
+        testing_env!(context(2, NOW).build());
+        contract.react(post.id, VoteStatus::Love);
+    }
+
+    #[test]
+    #[should_panic(expected = "You're banned from this community")]
+    fn test_banned_accounts_can_not_vote() {
+        let mut contract = setup();
+        let community_id = new_community(&mut contract, 1, "Rust", CommunityVisibility::Public);
+        let post = new_community_post(&mut contract, 1, NOW, &community_id);
+        contract.ban_member(community_id, accounts(2));
+
+        testing_env!(context(2, NOW).build());
+        contract.upvote(post.id);
+    }
+
+    #[test]
+    fn test_bookmarks_skip_deleted_and_private_posts() {
+        let mut contract = setup();
+        let kept = new_post(&mut contract, 1, NOW);
+        let deleted = new_post(&mut contract, 1, NOW + 1);
+        let community_id =
+            new_community(&mut contract, 1, "Rust", CommunityVisibility::RequestToJoin);
+        let private = new_community_post(&mut contract, 1, NOW + 2, &community_id);
+
+        testing_env!(context(2, NOW + 3).build());
+        for post in &[&kept, &deleted, &private] {
+            contract.add_bookmark(post.id.clone());
+        }
+        testing_env!(context(1, NOW + 4).build());
+        contract.delete_post(deleted.id);
+
+        let bookmarks = contract.get_bookmarks(accounts(2), 0, 10);
+        assert_eq!(bookmarks.len(), 1);
+        assert_eq!(bookmarks[0].id, kept.id);
     }
 }
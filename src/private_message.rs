@@ -24,59 +24,293 @@ impl Contract {
         sender_body: String,
         receiver_body: String,
     ) {
-        let id: String = if env::predecessor_account_id() > receiver_id.clone() {
-            env::predecessor_account_id() + "_" + &receiver_id.clone()
-        } else {
-            receiver_id.clone() + "_" + &env::predecessor_account_id()
-        };
-        env::log(format!("ID: {}", id).as_bytes());
+        let sender_id = env::predecessor_account_id();
+        let id = conversation_id(&sender_id, &receiver_id);
+        self.assert_not_blocked(&receiver_id, &sender_id);
+
+        let storage_update = self.new_storage_update(sender_id.clone());
 
-        match self.get_message(id.clone()) {
+        let last_message_height = match self.get_message(id.clone()) {
             Some(last_message) => {
-                let message = PrivateMessage {
-                    message_id: Some(id.clone()),
-                    sender_id: env::predecessor_account_id(),
-                    receiver_id: receiver_id.clone(),
-                    sender_body,
-                    receiver_body,
-                    time: env::block_timestamp(),
-                    block_height: env::block_index(),
-                    last_message_height: last_message.block_height,
-                };
-
-                if last_message.block_height == env::block_index() {
-                    env::panic(b"Can't post twice per block");
-                }
-
-                self.messages.remove(&id);
-                self.messages.insert(&id, &message);
+                assert!(
+                    last_message.block_height != env::block_index(),
+                    "Can't post twice per block"
+                );
+                last_message.block_height
             }
-
             None => {
-                let message = PrivateMessage {
-                    message_id: Some(id.clone()),
-                    sender_id: env::predecessor_account_id(),
-                    receiver_id: receiver_id.clone(),
-                    sender_body,
-                    receiver_body,
-                    time: env::block_timestamp(),
-                    block_height: env::block_index(),
-                    last_message_height: 0,
-                };
-                self.messages.insert(&id.clone(), &message);
-
-                let mut sender = self.internal_get_account(&env::predecessor_account_id());
+                let mut sender = self.internal_get_account(&sender_id);
                 sender.related_conversations.insert(&id);
-                self.internal_set_account(&env::predecessor_account_id(), sender);
+                self.internal_set_account(&sender_id, sender);
 
                 let mut receiver = self.internal_get_account(&receiver_id);
                 receiver.related_conversations.insert(&id);
                 self.internal_set_account(&receiver_id, receiver);
+
+                0
             }
-        }
+        };
+
+        let message = PrivateMessage {
+            message_id: Some(id.clone()),
+            sender_id: sender_id.clone(),
+            receiver_id: receiver_id.clone(),
+            sender_body,
+            receiver_body,
+            time: env::block_timestamp(),
+            block_height: env::block_index(),
+            last_message_height,
+        };
+        let mut history = self.conversation_messages.get(&id).unwrap_or_else(|| {
+            let mut history = Vector::new(StorageKey::ConversationMessagesInner { id: id.clone() });
+            // V1 conversations only kept their latest message.
+            if let Some(last_message) = self.messages.remove(&id) {
+                history.push(&last_message);
+            }
+            history
+        });
+        history.push(&message);
+        self.conversation_messages.insert(&id, &history);
+
+        // Sender has read its own message.
+        let message_index = history.len() - 1;
+        self.internal_set_read_receipt(&id, &sender_id, message_index);
+        self.internal_notify(
+            &receiver_id,
+            &sender_id,
+            NotificationKind::Message {
+                conversation_id: id.clone(),
+            },
+        );
+
+        self.finalize_storage_update(storage_update);
+
+        Event::MessageSend(MessageData {
+            conversation_id: &id,
+            message_index,
+            sender_id: &sender_id,
+            receiver_id: &receiver_id,
+        })
+        .emit();
     }
 
+    /// Latest message of the conversation.
     pub fn get_message(&self, message_id: MessageId) -> Option<PrivateMessage> {
-        self.messages.get(&message_id)
+        match self.conversation_messages.get(&message_id) {
+            Some(history) => history.get(history.len() - 1),
+            None => self.messages.get(&message_id),
+        }
+    }
+
+    /// Messages of the conversation, newest first.
+    pub fn get_conversation_messages(
+        &self,
+        conversation_id: MessageId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<PrivateMessage> {
+        match self.conversation_messages.get(&conversation_id) {
+            Some(history) => calculate_rev_limit(history.len(), from_index, limit)
+                .map(|index| history.get(index).unwrap())
+                .rev()
+                .collect(),
+            None => self
+                .messages
+                .get(&conversation_id)
+                .filter(|_| from_index == 0 && limit > 0)
+                .into_iter()
+                .collect(),
+        }
+    }
+
+    /// Works for both 1:1 and group conversations.
+    pub fn get_num_conversation_messages(&self, conversation_id: MessageId) -> u64 {
+        match self.conversation_messages.get(&conversation_id) {
+            Some(history) => history.len(),
+            None if self.messages.contains_key(&conversation_id) => 1,
+            None => self
+                .group_messages
+                .get(&conversation_id)
+                .map(|history| history.len())
+                .unwrap_or(0),
+        }
+    }
+
+    /// Marks every message up to `message_index` (inclusive) as read by the caller.
+    pub fn mark_conversation_read(&mut self, conversation_id: MessageId, message_index: u64) {
+        let account_id = env::predecessor_account_id();
+        let account = self.internal_get_account(&account_id);
+        assert!(
+            account.related_conversations.contains(&conversation_id),
+            "You're not a participant of this conversation"
+        );
+
+        let num_messages = self.get_num_conversation_messages(conversation_id.clone());
+        assert!(message_index < num_messages, "Message not found");
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        self.internal_set_read_receipt(&conversation_id, &account_id, message_index);
+        self.finalize_storage_update(storage_update);
+
+        Event::MessageRead(MessageReadData {
+            conversation_id: &conversation_id,
+            message_index,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    /// Index of the last message read by each participant.
+    pub fn get_read_receipts(&self, conversation_id: MessageId) -> Vec<(AccountId, U64)> {
+        match self.read_receipts.get(&conversation_id) {
+            Some(receipts) => receipts
+                .iter()
+                .map(|(account_id, index)| (account_id, index.into()))
+                .collect(),
+            None => vec![],
+        }
+    }
+
+    pub fn get_unread_count(&self, conversation_id: MessageId, account_id: AccountId) -> u64 {
+        let num_messages = self.get_num_conversation_messages(conversation_id.clone());
+        match self
+            .read_receipts
+            .get(&conversation_id)
+            .and_then(|receipts| receipts.get(&account_id))
+        {
+            Some(index) => num_messages - index - 1,
+            None => num_messages,
+        }
+    }
+}
+
+impl Contract {
+    /// Read receipts only move forward.
+    pub(crate) fn internal_set_read_receipt(
+        &mut self,
+        conversation_id: &MessageId,
+        account_id: &AccountId,
+        message_index: u64,
+    ) {
+        let mut receipts = self.read_receipts.get(conversation_id).unwrap_or_else(|| {
+            UnorderedMap::new(StorageKey::ReadReceiptsInner {
+                id: conversation_id.clone(),
+            })
+        });
+        if receipts.get(account_id).unwrap_or(0) <= message_index {
+            receipts.insert(account_id, &message_index);
+            self.read_receipts.insert(conversation_id, &receipts);
+        }
+    }
+}
+
+pub(crate) fn conversation_id(account_id: &str, other_account_id: &str) -> MessageId {
+    if account_id > other_account_id {
+        account_id.to_string() + "_" + other_account_id
+    } else {
+        other_account_id.to_string() + "_" + account_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    /// Message of `accounts(sender)` to `accounts(receiver)`, sent at block `block_index`.
+    fn send(contract: &mut Contract, sender: usize, receiver: usize, block_index: u64) {
+        testing_env!(context(sender, NOW + block_index)
+            .block_index(block_index)
+            .build());
+        contract.new_message(
+            accounts(receiver).into(),
+            format!("sender {}", block_index),
+            format!("receiver {}", block_index),
+        );
+    }
+
+    #[test]
+    fn test_conversation_history() {
+        let mut contract = setup();
+        send(&mut contract, 1, 2, 1);
+        send(&mut contract, 2, 1, 2);
+        send(&mut contract, 1, 2, 3);
+
+        let conversation_id = conversation_id("bob", "charlie");
+        assert_eq!(
+            contract.get_num_conversation_messages(conversation_id.clone()),
+            3
+        );
+        let messages = contract.get_conversation_messages(conversation_id.clone(), 0, 2);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].sender_body, "sender 3");
+        assert_eq!(messages[0].last_message_height, 2);
+        assert_eq!(messages[1].sender_body, "sender 2");
+        let older = contract.get_conversation_messages(conversation_id.clone(), 2, 2);
+        assert_eq!(older.len(), 1);
+        assert_eq!(older[0].sender_body, "sender 1");
+
+        let latest = contract.get_message(conversation_id).unwrap();
+        assert_eq!(latest.block_height, 3);
+        // The history is the only copy of the messages.
+        assert!(contract.messages.get(&latest.message_id.unwrap()).is_none());
+    }
+
+    #[test]
+    #[should_panic(expected = "Can't post twice per block")]
+    fn test_one_message_per_block() {
+        let mut contract = setup();
+        send(&mut contract, 1, 2, 1);
+        send(&mut contract, 2, 1, 1);
+    }
+
+    #[test]
+    fn test_read_receipts_and_unread_count() {
+        let mut contract = setup();
+        for block_index in 1..4 {
+            send(&mut contract, 1, 2, block_index);
+        }
+
+        let conversation_id = conversation_id("bob", "charlie");
+        assert_eq!(
+            contract.get_unread_count(conversation_id.clone(), "bob".to_string()),
+            0
+        );
+        assert_eq!(
+            contract.get_unread_count(conversation_id.clone(), "charlie".to_string()),
+            3
+        );
+
+        testing_env!(context(2, NOW).build());
+        contract.mark_conversation_read(conversation_id.clone(), 1);
+        assert_eq!(
+            contract.get_unread_count(conversation_id.clone(), "charlie".to_string()),
+            1
+        );
+        // Receipts never move back.
+        contract.mark_conversation_read(conversation_id.clone(), 0);
+        assert_eq!(
+            contract.get_unread_count(conversation_id.clone(), "charlie".to_string()),
+            1
+        );
+
+        let mut receipts = contract.get_read_receipts(conversation_id);
+        receipts.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            receipts
+                .into_iter()
+                .map(|(account_id, index)| (account_id, index.0))
+                .collect::<Vec<_>>(),
+            vec![("bob".to_string(), 2), ("charlie".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "You're not a participant of this conversation")]
+    fn test_only_participants_mark_read() {
+        let mut contract = setup();
+        send(&mut contract, 1, 2, 1);
+        testing_env!(context(3, NOW).build());
+        contract.mark_conversation_read(conversation_id("bob", "charlie"), 0);
     }
 }
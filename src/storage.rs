@@ -127,6 +127,7 @@ impl Contract {
 
 impl Contract {
     pub(crate) fn new_storage_update(&mut self, account_id: AccountId) -> StorageUpdate {
+        self.assert_migrated();
         let storage_account = self
             .storage_accounts
             .get(&account_id)
@@ -139,6 +140,21 @@ impl Contract {
         }
     }
 
+    /// Charges the bytes written since `initial_storage` to the account without checking its
+    /// balance, for callbacks that can't reject the promise they follow. The account tops up
+    /// before its next write.
+    pub(crate) fn internal_charge_storage(
+        &mut self,
+        account_id: &AccountId,
+        initial_storage: StorageUsage,
+    ) {
+        if let Some(mut storage_account) = self.storage_accounts.get(account_id) {
+            storage_account.used_bytes =
+                (storage_account.used_bytes + env::storage_usage()).saturating_sub(initial_storage);
+            self.storage_accounts.insert(account_id, &storage_account);
+        }
+    }
+
     pub(crate) fn finalize_storage_update(&mut self, storage_update: StorageUpdate) {
         let StorageUpdate {
             account_id,
@@ -0,0 +1,77 @@
+//! Fixture shared by the unit tests of every module.
+pub use near_sdk::test_utils::{accounts, VMContextBuilder};
+pub use near_sdk::{testing_env, MockedBlockchain};
+
+use crate::*;
+
+pub const IPFS_BODY: &str = "QmYwAPJzv5CZsnA625s3Xf2nemtYgPpHdWEz79ojWnPbdG";
+pub const NOW: u64 = 1_600_000_000;
+
+/// Call of `accounts(predecessor)` at `seconds`, with one NEAR attached.
+pub fn context(predecessor: usize, seconds: u64) -> VMContextBuilder {
+    let mut builder = VMContextBuilder::new();
+    builder
+        .current_account_id(accounts(0))
+        .predecessor_account_id(accounts(predecessor))
+        .block_timestamp(seconds * 1_000_000_000)
+        .attached_deposit(10u128.pow(24));
+    builder
+}
+
+/// Contract owned by alice, with alice, bob, charlie and danny registered. Leaves alice as
+/// the caller.
+pub fn setup() -> Contract {
+    testing_env!(context(0, NOW).build());
+    let mut contract = Contract::new(accounts(0), "nft.testnet".to_string());
+    for index in 0..4 {
+        testing_env!(context(index, NOW).build());
+        contract.storage_deposit(None);
+    }
+    testing_env!(context(0, NOW).build());
+    contract
+}
+
+/// Text post of `accounts(predecessor)` in the default topic.
+pub fn new_post(contract: &mut Contract, predecessor: usize, seconds: u64) -> Post {
+    testing_env!(context(predecessor, seconds).build());
+    contract.post(
+        "title".to_string(),
+        IPFS_BODY.to_string(),
+        PostType::Text,
+        vec!["default".to_string()],
+    )
+}
+
+/// Community named `name` created by `accounts(predecessor)`.
+pub fn new_community(
+    contract: &mut Contract,
+    predecessor: usize,
+    name: &str,
+    visibility: CommunityVisibility,
+) -> CommunityId {
+    testing_env!(context(predecessor, NOW).build());
+    contract.new_community(
+        None,
+        None,
+        name.to_string(),
+        "".to_string(),
+        Some(visibility),
+    )
+}
+
+/// Post of `accounts(predecessor)` in the community, in the default topic.
+pub fn new_community_post(
+    contract: &mut Contract,
+    predecessor: usize,
+    seconds: u64,
+    community_id: &CommunityId,
+) -> Post {
+    testing_env!(context(predecessor, seconds).build());
+    contract.community_post(
+        "title".to_string(),
+        IPFS_BODY.to_string(),
+        PostType::Text,
+        vec!["default".to_string()],
+        community_id.clone(),
+    )
+}
@@ -5,52 +5,111 @@ use super::*;
 pub struct Topic {
     pub id: TopicId,
     pub admin: ValidAccountId,
-    // thumbnail: String,
+    pub thumbnail: String,
     pub name: String,
     pub created_time: U64,
     pub description: String,
+    /// Archived topics are kept for existing posts but don't accept new ones.
+    pub archived: bool,
+    pub parent_id: Option<TopicId>,
 }
 
 pub type TopicId = String;
 
+/// Steps of `backfill_topic_posts`, run in this order.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum TopicBackfillStep {
+    /// Empties the topic indexes, `next_index` is the position of the topic being emptied.
+    Clear,
+    /// Indexes `posts` from the end, `next_index` is the position below which posts are left.
+    /// Posts deleted meanwhile swap a later post in, which is then indexed twice at worst.
+    Index,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct TopicBackfillProgress {
+    pub step: TopicBackfillStep,
+    /// `from_index` of the next `backfill_topic_posts` call.
+    pub next_index: u64,
+}
+
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct WrappedTopic {
+    #[serde(flatten)]
+    pub topic: Topic,
+
+    //stats
+    pub num_followers: u64,
+    pub num_posts: u64,
+}
+
 #[near_bindgen]
 impl Contract {
     // make topic name is a id. because we want topic is unique
+    /// Child topics are created by the parent admin, their id is prefixed by the parent id,
+    /// e.g. `dev/rust`.
     pub fn new_topic(
         &mut self,
         topic_name: String,
         // topic_thumbnail: String,
         topic_desc: String,
+        parent_id: Option<TopicId>,
     ) -> bool {
-        let topic_id = topic_name.to_lowercase().replace(' ', "_");
+        assert!(!topic_name.contains('/'), "Topic name can not contain '/'");
+        let account_id = env::predecessor_account_id();
+        let mut topic_id = topic_name.to_lowercase().replace(' ', "_");
+        if let Some(parent_id) = &parent_id {
+            let parent = self.internal_get_managed_topic(parent_id, &account_id);
+            assert!(!parent.archived, "This topic is archived");
+            assert!(
+                parent_id.matches('/').count() + 1 < MAX_TOPIC_DEPTH,
+                "Topics can not be nested more than {} levels",
+                MAX_TOPIC_DEPTH
+            );
+            let num_children = self
+                .topic_children
+                .get(parent_id)
+                .map(|children| children.len())
+                .unwrap_or(0);
+            assert!(
+                num_children < MAX_TOPIC_CHILDREN,
+                "A topic can not have more than {} child topics",
+                MAX_TOPIC_CHILDREN
+            );
+            topic_id = format!("{}/{}", parent_id, topic_id);
+        }
 
-        assert!(
-            topic_name.len() <= MAX_TITLE_LENGTH,
-            "Can not make a post title more than {} characters",
-            MAX_TITLE_LENGTH
-        );
-
-        assert!(
-            topic_desc.len() <= MAX_BODY_LENGTH,
-            "Can not make a post body more than {} characters",
-            MAX_BODY_LENGTH
-        );
+        assert_topic_name(&topic_name);
+        assert_topic_description(&topic_desc);
 
         assert!(self.topics.get(&topic_id).is_none(), "Topic already exists");
 
-        let account_id = env::predecessor_account_id();
         let storage_update = self.new_storage_update(account_id.clone());
 
         let topic = Topic {
             id: topic_id.clone(),
             name: topic_name,
             admin: ValidAccountId::try_from(account_id).unwrap(),
-            // thumbnail: topic_thumbnail,
+            thumbnail: "".to_string(),
             created_time: env::block_timestamp().into(),
             description: topic_desc,
+            archived: false,
+            parent_id: parent_id.clone(),
         };
 
         self.topics.insert(&topic_id, &topic);
+        if let Some(parent_id) = parent_id {
+            let mut children = self.topic_children.get(&parent_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TopicChildrenInner {
+                    id: parent_id.clone(),
+                })
+            });
+            children.insert(&topic_id);
+            self.topic_children.insert(&parent_id, &children);
+        }
         self.finalize_storage_update(storage_update);
 
         true
@@ -59,4 +118,558 @@ impl Contract {
     pub fn topics(&self) -> Vec<Topic> {
         self.topics.values().collect()
     }
+
+    /// Topic admin and topic curators only, the topic id doesn't change with its name.
+    pub fn update_topic(
+        &mut self,
+        topic_id: TopicId,
+        name: Option<String>,
+        description: Option<String>,
+        thumbnail: Option<String>,
+    ) -> Topic {
+        let account_id = env::predecessor_account_id();
+        let mut topic = self.internal_get_managed_topic(&topic_id, &account_id);
+
+        if let Some(name) = name {
+            assert_topic_name(&name);
+            topic.name = name;
+        }
+        if let Some(description) = description {
+            assert_topic_description(&description);
+            topic.description = description;
+        }
+        if let Some(thumbnail) = thumbnail {
+            assert!(
+                valid_url(thumbnail.clone()) || thumbnail.len() == IPFS_HASH_LENGTH,
+                "Thumbnail should be an url or an ipfs hash"
+            );
+            topic.thumbnail = thumbnail;
+        }
+
+        let storage_update = self.new_storage_update(account_id.clone());
+        self.topics.insert(&topic_id, &topic);
+        self.finalize_storage_update(storage_update);
+
+        Event::TopicUpdate(TopicData {
+            topic_id: &topic_id,
+            account_id: &account_id,
+        })
+        .emit();
+        topic
+    }
+
+    pub fn archive_topic(&mut self, topic_id: TopicId) {
+        self.assert_migrated();
+        let account_id = env::predecessor_account_id();
+        let mut topic = self.internal_get_managed_topic(&topic_id, &account_id);
+        assert!(!topic.archived, "This topic is already archived");
+
+        topic.archived = true;
+        self.topics.insert(&topic_id, &topic);
+
+        Event::TopicArchive(TopicData {
+            topic_id: &topic_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn unarchive_topic(&mut self, topic_id: TopicId) {
+        self.assert_migrated();
+        let account_id = env::predecessor_account_id();
+        let mut topic = self.internal_get_managed_topic(&topic_id, &account_id);
+        assert!(topic.archived, "This topic is not archived");
+
+        topic.archived = false;
+        self.topics.insert(&topic_id, &topic);
+
+        Event::TopicUnarchive(TopicData {
+            topic_id: &topic_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    /// Posts of followed topics are merged into the home feed.
+    pub fn follow_topic(&mut self, topic_id: TopicId) {
+        assert!(self.topics.get(&topic_id).is_some(), "Not found your topic");
+        let account_id = env::predecessor_account_id();
+        let storage_update = self.new_storage_update(account_id.clone());
+
+        let mut account = self.internal_get_account(&account_id);
+        assert!(
+            account.followed_topics.insert(&topic_id),
+            "You already follow this topic"
+        );
+        self.internal_set_account(&account_id, account);
+
+        let mut followers = self.topic_followers.get(&topic_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::TopicFollowersInner {
+                id: topic_id.clone(),
+            })
+        });
+        followers.insert(&account_id);
+        self.topic_followers.insert(&topic_id, &followers);
+        self.finalize_storage_update(storage_update);
+
+        Event::TopicFollow(TopicData {
+            topic_id: &topic_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn unfollow_topic(&mut self, topic_id: TopicId) {
+        let account_id = env::predecessor_account_id();
+        let storage_update = self.new_storage_update(account_id.clone());
+
+        let mut account = self.internal_get_account(&account_id);
+        assert!(
+            account.followed_topics.remove(&topic_id),
+            "You don't follow this topic"
+        );
+        self.internal_set_account(&account_id, account);
+
+        if let Some(mut followers) = self.topic_followers.get(&topic_id) {
+            followers.remove(&account_id);
+            self.topic_followers.insert(&topic_id, &followers);
+        }
+        self.finalize_storage_update(storage_update);
+
+        Event::TopicUnfollow(TopicData {
+            topic_id: &topic_id,
+            account_id: &account_id,
+        })
+        .emit();
+    }
+
+    pub fn get_topics(&self, from_index: u64, limit: u64) -> Vec<WrappedTopic> {
+        let topics = self.topics.values_as_vector();
+        (from_index..std::cmp::min(from_index + limit, topics.len()))
+            .map(|index| {
+                let topic = topics.get(index).unwrap();
+                WrappedTopic {
+                    num_followers: self
+                        .topic_followers
+                        .get(&topic.id)
+                        .map(|followers| followers.len())
+                        .unwrap_or(0),
+                    num_posts: self.get_num_posts_of_topic(topic.id.clone()),
+                    topic,
+                }
+            })
+            .collect()
+    }
+
+    pub fn get_followed_topics(
+        &self,
+        account_id: ValidAccountId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<TopicId> {
+        let account = self.internal_get_account(account_id.as_ref());
+        let topic_ids = account.followed_topics.as_vector();
+        (from_index..std::cmp::min(from_index + limit, topic_ids.len()))
+            .map(|index| topic_ids.get(index).unwrap())
+            .collect()
+    }
+
+    pub fn get_topic_children(
+        &self,
+        topic_id: TopicId,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<TopicId> {
+        match self.topic_children.get(&topic_id) {
+            Some(children) => {
+                let children = children.as_vector();
+                (from_index..std::cmp::min(from_index + limit, children.len()))
+                    .map(|index| children.get(index).unwrap())
+                    .collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Number of live posts tagged with the topic itself, child topics are not counted.
+    pub fn get_num_posts_of_topic(&self, topic_id: TopicId) -> u64 {
+        self.topics_posts
+            .get(&topic_id)
+            .map(|posts| posts.len())
+            .unwrap_or(0)
+    }
+
+    /// Owner and admins only. Rebuilds the topic indexes from `posts`, each call removes or
+    /// indexes at most `limit` entries. Start with the `Clear` step at index 0 and pass the
+    /// returned progress until it is `None`.
+    pub fn backfill_topic_posts(
+        &mut self,
+        step: TopicBackfillStep,
+        from_index: u64,
+        limit: u64,
+    ) -> Option<TopicBackfillProgress> {
+        self.assert_migrated();
+        let caller_id = env::predecessor_account_id();
+        assert!(
+            caller_id == self.owner_id || self.is_admin(caller_id),
+            "You not have permission to backfill topics"
+        );
+
+        match step {
+            TopicBackfillStep::Clear => Some(self.internal_clear_topic_posts(from_index, limit)),
+            TopicBackfillStep::Index => self.internal_backfill_topic_posts(from_index, limit),
+        }
+    }
+}
+
+impl Contract {
+    /// Topics of a new post, they must exist and accept new posts.
+    pub(crate) fn assert_post_topics(&self, topic_ids: &[TopicId]) {
+        assert!(
+            !topic_ids.is_empty() && topic_ids.len() <= MAX_POST_TOPICS,
+            "Post should have between 1 and {} topics",
+            MAX_POST_TOPICS
+        );
+        for (index, topic_id) in topic_ids.iter().enumerate() {
+            assert!(
+                !topic_ids[..index].contains(topic_id),
+                "Duplicated topic {}",
+                topic_id
+            );
+            let topic = self.topics.get(topic_id).expect("Not found your topic");
+            assert!(!topic.archived, "This topic is archived");
+        }
+    }
+
+    /// `topic_id` followed by its descendants, at most `MAX_TOPIC_CHILDREN` per topic over
+    /// `MAX_TOPIC_DEPTH` levels.
+    pub(crate) fn internal_get_topic_tree(&self, topic_id: &TopicId) -> Vec<TopicId> {
+        let mut topic_ids = vec![topic_id.clone()];
+        let mut index = 0;
+        while index < topic_ids.len() {
+            if let Some(children) = self.topic_children.get(&topic_ids[index]) {
+                topic_ids.extend(children.iter());
+            }
+            index += 1;
+        }
+        topic_ids
+    }
+
+    fn internal_get_managed_topic(&self, topic_id: &TopicId, account_id: &AccountId) -> Topic {
+        let topic = self.topics.get(topic_id).expect("Not found your topic");
+        assert!(
+            topic.admin.as_ref() == account_id
+                || self.has_role(account_id.clone(), Role::TopicCurator),
+            "You don't have permission to manage this topic"
+        );
+        topic
+    }
+
+    /// Topics already listing the post are skipped, so a rebuild can index a post twice.
+    pub(crate) fn internal_insert_topic_post(&mut self, post: &Post) {
+        for topic_id in &post.topics {
+            let mut topic_posts = self.topics_posts.get(topic_id).unwrap_or_else(|| {
+                UnorderedSet::new(StorageKey::TopicsPostsInner {
+                    id: topic_id.clone(),
+                })
+            });
+            if topic_posts.insert(&post.id) {
+                self.topics_posts.insert(topic_id, &topic_posts);
+                self.internal_index_topic_post(topic_id, post);
+            }
+        }
+    }
+
+    /// A topic with nothing left to remove counts as one entry so empty topics are paged too.
+    fn internal_clear_topic_posts(&mut self, from_index: u64, limit: u64) -> TopicBackfillProgress {
+        let topic_ids = self.topics.keys_as_vector();
+        let mut index = from_index;
+        let mut removed = 0;
+        while removed < limit && index < topic_ids.len() {
+            let topic_id = topic_ids.get(index).unwrap();
+            let visited = removed;
+            let mut done = true;
+            if let Some(mut topic_posts) = self.topics_posts.get(&topic_id) {
+                removed += remove_last_elements(&mut topic_posts, limit - removed);
+                self.topics_posts.insert(&topic_id, &topic_posts);
+                done &= topic_posts.is_empty();
+            }
+            if let Some(mut topic_posts) = self.topic_post_index.get(&topic_id) {
+                while removed < limit && topic_posts.pop().is_some() {
+                    removed += 1;
+                }
+                self.topic_post_index.insert(&topic_id, &topic_posts);
+                done &= topic_posts.is_empty();
+            }
+            if removed == visited {
+                removed += 1;
+            }
+            if done {
+                index += 1;
+            }
+        }
+
+        if index < topic_ids.len() {
+            TopicBackfillProgress {
+                step: TopicBackfillStep::Clear,
+                next_index: index,
+            }
+        } else {
+            TopicBackfillProgress {
+                step: TopicBackfillStep::Index,
+                next_index: self.posts.len(),
+            }
+        }
+    }
+
+    fn internal_backfill_topic_posts(
+        &mut self,
+        from_index: u64,
+        limit: u64,
+    ) -> Option<TopicBackfillProgress> {
+        let to = std::cmp::min(from_index, self.posts.len());
+        let from = to.saturating_sub(limit);
+        for index in (from..to).rev() {
+            let post: Post = self.posts.values_as_vector().get(index).unwrap().into();
+            self.internal_insert_topic_post(&post);
+        }
+        if from == 0 {
+            None
+        } else {
+            Some(TopicBackfillProgress {
+                step: TopicBackfillStep::Index,
+                next_index: from,
+            })
+        }
+    }
+
+    pub(crate) fn internal_remove_topic_post(&mut self, post: &Post) {
+        for topic_id in &post.topics {
+            if let Some(mut topic_posts) = self.topics_posts.get(topic_id) {
+                topic_posts.remove(&post.id);
+                self.topics_posts.insert(topic_id, &topic_posts);
+            }
+        }
+    }
+}
+
+fn assert_topic_name(name: &str) {
+    assert!(
+        name.len() <= MAX_TITLE_LENGTH,
+        "Can not make a topic name more than {} characters",
+        MAX_TITLE_LENGTH
+    );
+}
+
+fn assert_topic_description(description: &str) {
+    assert!(
+        description.len() <= MAX_BODY_LENGTH,
+        "Can not make a topic description more than {} characters",
+        MAX_BODY_LENGTH
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::*;
+
+    fn setup_topic() -> Contract {
+        let mut contract = setup();
+        testing_env!(context(1, NOW).build());
+        contract.new_topic("Rust".to_string(), "".to_string(), None);
+        contract
+    }
+
+    fn new_rust_post(contract: &mut Contract, seconds: u64) -> Post {
+        testing_env!(context(1, seconds).build());
+        contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["rust".to_string()],
+        )
+    }
+
+    #[test]
+    fn test_follow_topic_feed_and_listing() {
+        let mut contract = setup_topic();
+        contract.update_topic(
+            "rust".to_string(),
+            Some("Rust Lang".to_string()),
+            None,
+            Some(IPFS_BODY.to_string()),
+        );
+        let post = new_rust_post(&mut contract, 1_600_000_001);
+
+        testing_env!(context(2, 1_600_000_002).build());
+        contract.follow_topic("rust".to_string());
+        let feed = contract.get_home_feed(accounts(2), None, 10);
+        assert_eq!(feed.posts[0].id, post.id);
+        assert_eq!(
+            contract.get_followed_topics(accounts(2), 0, 10),
+            vec!["rust".to_string()]
+        );
+
+        let topics = contract.get_topics(1, 10);
+        assert_eq!(topics[0].topic.name, "Rust Lang");
+        assert_eq!(topics[0].topic.thumbnail, IPFS_BODY);
+        assert_eq!(topics[0].num_followers, 1);
+        assert_eq!(topics[0].num_posts, 1);
+
+        contract.unfollow_topic("rust".to_string());
+        assert!(contract
+            .get_home_feed(accounts(2), None, 10)
+            .posts
+            .is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "This topic is archived")]
+    fn test_archived_topic_rejects_reposts() {
+        let mut contract = setup_topic();
+        let post = new_rust_post(&mut contract, NOW + 1);
+        contract.archive_topic("rust".to_string());
+
+        testing_env!(context(2, NOW + 2).build());
+        contract.repost(post.id, None);
+    }
+
+    #[test]
+    #[should_panic(expected = "A topic can not have more than 10 child topics")]
+    fn test_topic_children_are_bounded() {
+        let mut contract = setup_topic();
+        for index in 0..=MAX_TOPIC_CHILDREN {
+            contract.new_topic(
+                format!("child{}", index),
+                "".to_string(),
+                Some("rust".to_string()),
+            );
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "This topic is archived")]
+    fn test_archived_topic_rejects_posts() {
+        let mut contract = setup_topic();
+        contract.archive_topic("rust".to_string());
+        new_rust_post(&mut contract, 1_600_000_001);
+    }
+
+    #[test]
+    #[should_panic(expected = "You don't have permission to manage this topic")]
+    fn test_only_topic_admin_can_edit() {
+        let mut contract = setup_topic();
+        testing_env!(context(2, 1_600_000_001).build());
+        contract.archive_topic("rust".to_string());
+    }
+
+    #[test]
+    fn test_child_topics_in_parent_feed() {
+        let mut contract = setup_topic();
+        contract.new_topic(
+            "Async".to_string(),
+            "".to_string(),
+            Some("rust".to_string()),
+        );
+        assert_eq!(
+            contract.get_topic_children("rust".to_string(), 0, 10),
+            vec!["rust/async".to_string()]
+        );
+
+        let parent_post = new_rust_post(&mut contract, 1_600_000_001);
+        testing_env!(context(1, 1_600_000_002).build());
+        let child_post = contract.post(
+            "title".to_string(),
+            IPFS_BODY.to_string(),
+            PostType::Text,
+            vec!["rust/async".to_string(), "default".to_string()],
+        );
+        assert_eq!(child_post.topics.len(), 2);
+
+        let feed = contract.get_topic_feed("rust".to_string(), None, 10);
+        let ids: Vec<PostId> = feed.posts.into_iter().map(|post| post.id).collect();
+        assert_eq!(ids, vec![child_post.id.clone(), parent_post.id]);
+        assert_eq!(
+            contract
+                .get_topic_feed("default".to_string(), None, 10)
+                .posts[0]
+                .id,
+            child_post.id
+        );
+        assert_eq!(contract.get_num_posts_of_topic("rust".to_string()), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "Thumbnail should be an url or an ipfs hash")]
+    fn test_topic_thumbnail_is_validated() {
+        let mut contract = setup_topic();
+        contract.update_topic(
+            "rust".to_string(),
+            None,
+            None,
+            Some("thumbnail".to_string()),
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "Can not make a topic description more than")]
+    fn test_topic_description_is_bounded() {
+        let mut contract = setup_topic();
+        contract.update_topic(
+            "rust".to_string(),
+            None,
+            Some("a".repeat(MAX_BODY_LENGTH + 1)),
+            None,
+        );
+    }
+
+    #[test]
+    fn test_backfill_is_paged_and_survives_deletes() {
+        let mut contract = setup_topic();
+        let posts: Vec<Post> = (1..5)
+            .map(|seconds| new_rust_post(&mut contract, NOW + seconds))
+            .collect();
+
+        testing_env!(context(0, NOW + 5).build());
+        let mut progress = contract.backfill_topic_posts(TopicBackfillStep::Clear, 0, 1);
+        while let Some(TopicBackfillProgress {
+            step: TopicBackfillStep::Clear,
+            next_index,
+        }) = progress
+        {
+            progress = contract.backfill_topic_posts(TopicBackfillStep::Clear, next_index, 1);
+        }
+        assert_eq!(
+            progress,
+            Some(TopicBackfillProgress {
+                step: TopicBackfillStep::Index,
+                next_index: 4
+            })
+        );
+        assert_eq!(contract.get_num_posts_of_topic("rust".to_string()), 0);
+
+        // The last post is indexed, then deleting the second one swaps it into position 1.
+        let progress = contract
+            .backfill_topic_posts(TopicBackfillStep::Index, 4, 1)
+            .unwrap();
+        testing_env!(context(1, NOW + 6).build());
+        contract.delete_post(posts[1].id.clone());
+        testing_env!(context(0, NOW + 7).build());
+        assert!(contract
+            .backfill_topic_posts(progress.step, progress.next_index, 10)
+            .is_none());
+
+        assert_eq!(contract.get_num_posts_of_topic("rust".to_string()), 3);
+        let feed = contract.get_topic_feed("rust".to_string(), None, 10);
+        let ids: Vec<PostId> = feed.posts.into_iter().map(|post| post.id).collect();
+        assert_eq!(
+            ids,
+            vec![
+                posts[3].id.clone(),
+                posts[2].id.clone(),
+                posts[0].id.clone()
+            ]
+        );
+    }
 }
@@ -24,7 +24,10 @@ pub(crate) enum StorageKey {
     TopicsPostsInner { id: String },
 
     Communities,
+    /// Only written by the V1 layout, kept so the following variants keep their prefixes.
+    #[allow(dead_code)]
     CommunitiesPosts,
+    #[allow(dead_code)]
     CommunitiesPostsInner { id: String },
 
     MemberInCommunites,
@@ -35,6 +38,65 @@ pub(crate) enum StorageKey {
     ChestsPerPlace,
     ChestsAtPlace { id: String },
     Chests,
+
+    CommentReplies,
+    CommentRepliesInner { post_id: PostId, comment_id: CommentId },
+
+    Roles,
+    RolesInner { role: Role },
+
+    CommunityModerators,
+    CommunityModeratorsInner { id: String },
+    BannedInCommunities,
+    BannedInCommunitiesInner { id: String },
+    CommunityJoinRequests,
+    CommunityJoinRequestsInner { id: String },
+    CommunityInvites,
+    CommunityInvitesInner { id: String },
+    ConversationMessages,
+    ConversationMessagesInner { id: String },
+    ReadReceipts,
+    ReadReceiptsInner { id: String },
+    GroupConversations,
+    GroupMembers,
+    GroupMembersInner { id: String },
+    GroupMessages,
+    GroupMessagesInner { id: String },
+    PostIndex,
+    AccountPostIndex,
+    AccountPostIndexInner { id: String },
+    CommunityPostIndex,
+    CommunityPostIndexInner { id: String },
+    VoteCounts,
+    Leaderboard,
+    ReactionCounts,
+    CommentVotes,
+    CommentVotesInner { post_id: PostId, comment_id: CommentId },
+    PostRevisions,
+    PostRevisionsInner { id: PostId },
+    PollVotes,
+    PollVotesInner { id: PostId },
+    PollTallies,
+    CommunityPosts,
+    CommunityPostsInner { id: CommunityId },
+    TopicPostIndex,
+    TopicPostIndexInner { id: TopicId },
+    TopicFollowers,
+    TopicFollowersInner { id: TopicId },
+    TopicChildren,
+    TopicChildrenInner { id: TopicId },
+    HashtagPostIndex,
+    HashtagPostIndexInner { hashtag: String },
+    MentionIndex,
+    MentionIndexInner { id: AccountId },
+    Notifications,
+    NotificationsInner { id: AccountId },
+    UserLeaderboard,
+    TopComments,
+    HashtagPosts,
+    MentionPosts,
+    SentNotifications,
+    Commenters,
 }
 
 pub fn valid_url(maybe_url: String) -> bool {
@@ -52,11 +114,28 @@ pub fn calculate_rev_limit(len: u64, from_index: u64, limit: u64) -> Range<u64>
         0
     };
 
-    let to = if len > from_index {
-        len - from_index
-    } else {
-        0
-    };
+    let to = len.saturating_sub(from_index);
 
     from..to
 }
+
+/// Storage prefix of a collection owned by `account_id`.
+pub fn account_prefix(prefix: u8, account_id: &str) -> Vec<u8> {
+    let mut key = vec![prefix];
+    key.extend(env::sha256(account_id.as_bytes()));
+    key
+}
+
+/// Removes up to `limit` elements from the end of the set, returns how many were removed.
+pub(crate) fn remove_last_elements<T>(set: &mut UnorderedSet<T>, limit: u64) -> u64
+where
+    T: BorshSerialize + BorshDeserialize,
+{
+    let mut removed = 0;
+    while removed < limit && !set.is_empty() {
+        let element = set.as_vector().get(set.len() - 1).unwrap();
+        set.remove(&element);
+        removed += 1;
+    }
+    removed
+}